@@ -2,35 +2,32 @@
 setTimeout(() => {
     // we need to wait for the svg to load, this is kind of painful...
     const plotWrapper = document.getElementById("my_plot");
-    const svg = plotWrapper.firstChild;
-    plotWrapper.style.display = 'flex';
-    plotWrapper.style.flexDirection = 'column';
-    plotWrapper.style.alignItems = 'center';
-
-    const plotContainer = plotWrapper.appendChild(document.createElement('div'));
-    plotContainer.style.position = 'relative';
-    plotContainer.appendChild(svg);
-    const tooltip = document.getElementById('my_plot_tooltip')
-    tooltip.style.display = 'block';
+    const plotContainer = document.getElementById("my_plot_container");
+    const tooltip = document.getElementById('my_plot_tooltip');
     tooltip.style.position = 'absolute';
-    plotContainer.appendChild(tooltip);
 
-  const circles = document.querySelectorAll('#my_plot circle');
-    console.log('value from js', circles);
-    console.log('Circle count from js: ', circles.length);
+    // the page replaces the svg when its controls change, so the circles are
+    // looked up again each time
+    let kdtree = null;
+    let focusedPoint = null;
+    function indexCircles() {
+        const circles = plotWrapper.querySelectorAll('circle');
+        const positionedCircles = [];
+        for (const circle of circles) {
+            const x = circle.cx.baseVal.value;
+            const y = circle.cy.baseVal.value;
+            positionedCircles.push({circle, x, y})
+        }
+        positionedCircles.sort((a, b) => a.x - b.x);
 
-    const positionedCircles = [];
-    for (const circle of circles) {
-        const x = circle.cx.baseVal.value;
-        const y = circle.cy.baseVal.value;
-        positionedCircles.push({circle, x, y})
+        kdtree = new KDTree(positionedCircles);
+        focusedPoint = null;
+        tooltip.style.display = 'none';
     }
-    positionedCircles.sort((a, b) => a.x - b.x);
-
-    const kdtree = new KDTree(positionedCircles);
+    indexCircles();
+    new MutationObserver(indexCircles).observe(plotWrapper, {childList: true});
 
     // enlarge the actual circle element on hover
-    let focusedPoint = null;
     /** @param svgCircle {SVGCircleElement}*/
     function focusPoint(svgCircle, x, y) {
       if (focusedPoint === svgCircle) {
@@ -53,16 +50,19 @@ setTimeout(() => {
         const closestCircle = kdtree.findNearest(
           clientX - plotClientX,
           clientY - plotClientY);
+        if (!closestCircle) {
+          return;
+        }
   
         // --- Update the tooltip ---
+        tooltip.style.display = 'block';
         tooltip.style.left = closestCircle.x + 'px';
         tooltip.style.top = closestCircle.y + 'px';
 
-        const xData = closestCircle.circle.attributes.getNamedItem('data-x').value;
-        const xAsDate = new Date(xData);
-        const xDataFormatted = xAsDate.getFullYear() + '-' + (xAsDate.getMonth() + 1) + '-' + xAsDate.getDate();
-        const yData = closestCircle.circle.attributes.getNamedItem('data-y').value;
-        tooltip.innerText = `(${xDataFormatted}, ${yData})`
+        // both are written by the chart in the page's locale
+        const xLabel = closestCircle.circle.getAttribute('data-label');
+        const yData = closestCircle.circle.getAttribute('data-y');
+        tooltip.innerText = `(${xLabel}, ${yData})`
         
         // --- style the point as focused (and deselect prev) ---
         focusPoint(closestCircle.circle, closestCircle.x, closestCircle.y);
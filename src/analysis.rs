@@ -0,0 +1,142 @@
+//! Statistics derived from a mod's download series.
+//!
+//! Everything here is pure computation over [`TimestampedModStats`], so it
+//! runs the same during SSR, in the browser and in the server's other
+//! endpoints.
+
+pub mod anomalies;
+pub mod compare;
+pub mod gaps;
+pub mod growth;
+pub mod resample;
+pub mod transform;
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::DateTime;
+use fibermc_sdk::models::{ModStatsResponse, TimestampedModStats};
+use serde::Serialize;
+
+pub const MS_PER_DAY: i64 = 86_400_000;
+
+/// One of the download series in a [`ModStatsResponse`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum Source {
+    Overall,
+    Modrinth,
+    CurseForge,
+}
+
+impl Source {
+    pub const ALL: [Source; 3] =
+        [Source::Overall, Source::Modrinth, Source::CurseForge];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Source::Overall => "overall",
+            Source::Modrinth => "modrinth",
+            Source::CurseForge => "curseforge",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::Overall => "Overall",
+            Source::Modrinth => "Modrinth",
+            Source::CurseForge => "CurseForge",
+        }
+    }
+
+    pub fn series(self, stats: &ModStatsResponse) -> &[TimestampedModStats] {
+        match self {
+            Source::Overall => &stats.overall_stats,
+            Source::Modrinth => &stats.modrinth_stats,
+            Source::CurseForge => &stats.curse_forge_stats,
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Source::ALL
+            .into_iter()
+            .find(|source| source.as_str() == s)
+            .ok_or_else(|| format!("unknown source '{s}'"))
+    }
+}
+
+/// The series as `(unix time in milliseconds, downloads)` pairs in time order.
+/// Points with unparseable timestamps are dropped.
+pub fn sorted_points(series: &[TimestampedModStats]) -> Vec<(i64, i64)> {
+    let mut points: Vec<(i64, i64)> = series
+        .iter()
+        .filter_map(|s| {
+            DateTime::parse_from_rfc3339(&s.timestamp)
+                .ok()
+                .map(|t| (t.timestamp_millis(), s.downloads))
+        })
+        .collect();
+    points.sort_by_key(|(t, _)| *t);
+    points
+}
+
+/// Cumulative downloads at time `t`, interpolated linearly between the points
+/// either side of it. `None` if `t` is outside the series.
+pub fn downloads_at(points: &[(i64, i64)], t: i64) -> Option<f64> {
+    let after = points.partition_point(|&(at, _)| at < t);
+    let &(t1, d1) = points.get(after)?;
+    if t1 == t {
+        return Some(d1 as f64);
+    }
+    let &(t0, d0) = points.get(after.checked_sub(1)?)?;
+    let progress = (t - t0) as f64 / (t1 - t0) as f64;
+    Some(d0 as f64 + (d1 - d0) as f64 * progress)
+}
+
+/// The median of `values`, which are reordered. `None` if empty.
+pub fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    Some(match values.len() % 2 {
+        0 => (values[mid - 1] + values[mid]) / 2.0,
+        _ => values[mid],
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use chrono::DateTime;
+
+    use super::MS_PER_DAY;
+
+    /// Unix time in milliseconds of an RFC 3339 timestamp.
+    pub fn at(timestamp: &str) -> i64 {
+        DateTime::parse_from_rfc3339(timestamp)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    /// Points a day apart from `start`, beginning at no downloads, with
+    /// `downloads[i]` new downloads over day `i`.
+    pub fn daily(start: i64, downloads: &[i64]) -> Vec<(i64, i64)> {
+        let mut total = 0;
+        let mut points = vec![(start, 0)];
+        for (day, new) in downloads.iter().enumerate() {
+            total += new;
+            points.push((start + (day as i64 + 1) * MS_PER_DAY, total));
+        }
+        points
+    }
+}
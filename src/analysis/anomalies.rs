@@ -0,0 +1,292 @@
+//! Detection of unusual stretches in download series: spikes (a feature in a
+//! video, inclusion in a modpack), flat periods (scraper outages) and drops
+//! (counts going backwards, which a cumulative total never should).
+//!
+//! Each interval between consecutive points is turned into a daily download
+//! rate and compared against the rates of the intervals around it, using the
+//! median and median absolute deviation (MAD) so that one spike doesn't hide
+//! its neighbours. Consecutive flagged intervals are merged into one range.
+
+use fibermc_sdk::models::ModStatsResponse;
+
+use super::gaps::{self, split_at_gaps};
+use super::{median, sorted_points, Source, MS_PER_DAY};
+use crate::format::Locale;
+
+/// Intervals compared against on each side of the one being checked.
+const WINDOW: usize = 7;
+/// Fewest neighbouring intervals needed for a judgement.
+const MIN_NEIGHBOURS: usize = 4;
+/// Modified z-score above which a rate counts as a spike (Iglewicz and
+/// Hoaglin's recommended cut-off).
+const SPIKE_Z_SCORE: f64 = 3.5;
+/// Scales the MAD to match a standard deviation for normal data.
+const MAD_SCALE: f64 = 0.6745;
+/// Lowest typical rate, in downloads per day, at which a stretch with no new
+/// downloads is suspicious rather than just a quiet mod.
+const FLAT_MIN_TYPICAL_RATE: f64 = 1.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnomalyKind {
+    Spike,
+    Flat,
+    Drop,
+}
+
+impl AnomalyKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            AnomalyKind::Spike => "Spike",
+            AnomalyKind::Flat => "Flat period",
+            AnomalyKind::Drop => "Drop",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Anomaly {
+    pub source: Source,
+    pub kind: AnomalyKind,
+    /// Unix time in milliseconds the range starts at.
+    pub start: i64,
+    /// Unix time in milliseconds the range ends at.
+    pub end: i64,
+    /// Change in downloads across the range.
+    pub change: i64,
+    /// The most extreme daily rate within the range.
+    pub rate: f64,
+    /// Typical daily rate around the range.
+    pub typical_rate: f64,
+}
+
+impl Anomaly {
+    /// How far out of the ordinary the range is, written for `locale`, e.g.
+    /// "8.2× the typical rate (+41,000 downloads)".
+    pub fn describe(&self, locale: Locale) -> String {
+        let days = (self.end - self.start) as f64 / MS_PER_DAY as f64;
+        match self.kind {
+            AnomalyKind::Spike if self.typical_rate > 0.0 => format!(
+                "{}× the typical rate of {}/day (+{} downloads)",
+                locale.decimal(self.rate / self.typical_rate, 1),
+                locale.decimal(self.typical_rate, 0),
+                locale.number(self.change)
+            ),
+            AnomalyKind::Spike => {
+                format!(
+                    "{}/day, up from none (+{} downloads)",
+                    locale.decimal(self.rate, 0),
+                    locale.number(self.change)
+                )
+            }
+            AnomalyKind::Flat => format!(
+                "no new downloads for {} days, against a typical {}/day",
+                locale.decimal(days, 1),
+                locale.decimal(self.typical_rate, 0)
+            ),
+            AnomalyKind::Drop => {
+                format!(
+                    "total fell by {} downloads",
+                    locale.number(-self.change)
+                )
+            }
+        }
+    }
+}
+
+/// Anomalies in every series of `stats`, in time order, leaving out the gaps
+/// found at `gap_factor`.
+pub fn detect(stats: &ModStatsResponse, gap_factor: f64) -> Vec<Anomaly> {
+    let mut anomalies: Vec<Anomaly> = Source::ALL
+        .into_iter()
+        .flat_map(|source| {
+            let points = sorted_points(source.series(stats));
+            detect_in_points(source, &points, gap_factor)
+        })
+        .collect();
+    anomalies.sort_by_key(|a| (a.start, a.end));
+    anomalies
+}
+
+/// Anomalies between the time-ordered `points` of one series, in time order.
+/// Each run between the gaps found at `gap_factor` is checked on its own, so
+/// an outage is reported as a gap rather than also as a flat period.
+pub fn detect_in_points(
+    source: Source,
+    points: &[(i64, i64)],
+    gap_factor: f64,
+) -> Vec<Anomaly> {
+    let gaps = gaps::detect_in_points(source, points, gap_factor);
+    split_at_gaps(points, &gaps)
+        .iter()
+        .flat_map(|run| detect_in_run(source, run))
+        .collect()
+}
+
+/// Anomalies within a run of `points` with no gaps, in time order.
+fn detect_in_run(source: Source, points: &[(i64, i64)]) -> Vec<Anomaly> {
+    // (start, end, change, downloads per day) of each interval
+    let intervals: Vec<(i64, i64, i64, f64)> = points
+        .windows(2)
+        .filter(|pair| pair[1].0 > pair[0].0)
+        .map(|pair| {
+            let ((t0, d0), (t1, d1)) = (pair[0], pair[1]);
+            let change = d1 - d0;
+            let rate = change as f64 * MS_PER_DAY as f64 / (t1 - t0) as f64;
+            (t0, t1, change, rate)
+        })
+        .collect();
+
+    let mut anomalies: Vec<Anomaly> = Vec::new();
+    for (idx, &(start, end, change, rate)) in intervals.iter().enumerate() {
+        let window =
+            idx.saturating_sub(WINDOW)..(idx + WINDOW + 1).min(intervals.len());
+        let mut neighbours: Vec<f64> = window
+            .filter(|&other| other != idx)
+            .map(|other| intervals[other].3)
+            .collect();
+        if neighbours.len() < MIN_NEIGHBOURS {
+            continue;
+        }
+        let Some(typical_rate) = median(&mut neighbours) else {
+            continue;
+        };
+        let mut deviations: Vec<f64> = neighbours
+            .iter()
+            .map(|r| (r - typical_rate).abs())
+            .collect();
+        // a perfectly steady neighbourhood has no spread; fall back to a
+        // small share of the rate so steady growth doesn't flag everything
+        let spread = median(&mut deviations)
+            .unwrap_or_default()
+            .max(typical_rate.abs() * 0.05)
+            .max(1.0);
+
+        let kind = if change < 0 {
+            AnomalyKind::Drop
+        } else if change == 0 && typical_rate >= FLAT_MIN_TYPICAL_RATE {
+            AnomalyKind::Flat
+        } else if MAD_SCALE * (rate - typical_rate) / spread > SPIKE_Z_SCORE {
+            AnomalyKind::Spike
+        } else {
+            continue;
+        };
+
+        match anomalies.last_mut() {
+            Some(last) if last.kind == kind && last.end == start => {
+                last.end = end;
+                last.change += change;
+                last.typical_rate = last.typical_rate.max(typical_rate);
+                last.rate = match kind {
+                    AnomalyKind::Spike => last.rate.max(rate),
+                    _ => last.rate.min(rate),
+                };
+            }
+            _ => anomalies.push(Anomaly {
+                source,
+                kind,
+                start,
+                end,
+                change,
+                rate,
+                typical_rate,
+            }),
+        }
+    }
+    anomalies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::gaps::DEFAULT_GAP_FACTOR;
+    use crate::analysis::testing::daily;
+
+    /// Anomalies as their kind and start and end day.
+    type Ranges = Vec<(AnomalyKind, i64, i64)>;
+
+    /// Three weeks of `steady` downloads a day, with `day` changed to `odd`.
+    fn with_day(steady: &[i64], day: usize, odd: i64) -> Vec<i64> {
+        let mut days: Vec<i64> =
+            steady.iter().copied().cycle().take(21).collect();
+        days[day] = odd;
+        days
+    }
+
+    #[test]
+    fn flags_rates_against_the_median_and_mad() {
+        use AnomalyKind::*;
+
+        // a steady neighbourhood has no spread, so the floor of 5% of the
+        // typical rate applies: a spike needs 0.6745 * (r - 100) / 5 > 3.5,
+        // so r > 125.9. Around 90, 100 and 110 the MAD is 10, so r > 151.9.
+        let cases: Vec<(&str, Vec<i64>, Ranges)> = vec![
+            ("steady", vec![100; 21], vec![]),
+            (
+                "under the steady cut-off",
+                with_day(&[100], 10, 125),
+                vec![],
+            ),
+            (
+                "over the steady cut-off",
+                with_day(&[100], 10, 126),
+                vec![(Spike, 10, 11)],
+            ),
+            (
+                "under the spread cut-off",
+                with_day(&[90, 100, 110], 10, 151),
+                vec![],
+            ),
+            (
+                "over the spread cut-off",
+                with_day(&[90, 100, 110], 10, 152),
+                vec![(Spike, 10, 11)],
+            ),
+            (
+                "no downloads amid steady ones",
+                with_day(&[100], 10, 0),
+                vec![(Flat, 10, 11)],
+            ),
+            ("no downloads ever", vec![0; 21], vec![]),
+            (
+                "total going backwards",
+                with_day(&[100], 10, -50),
+                vec![(Drop, 10, 11)],
+            ),
+            (
+                "consecutive spikes merge",
+                {
+                    let mut days = with_day(&[100], 10, 1000);
+                    days[11] = 1000;
+                    days
+                },
+                vec![(Spike, 10, 12)],
+            ),
+        ];
+
+        for (name, downloads, expected) in cases {
+            let found: Ranges = detect_in_points(
+                Source::Overall,
+                &daily(0, &downloads),
+                DEFAULT_GAP_FACTOR,
+            )
+            .into_iter()
+            .map(|a| (a.kind, a.start / MS_PER_DAY, a.end / MS_PER_DAY))
+            .collect();
+            assert_eq!(found, expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn outage_is_a_gap_not_a_flat_period() {
+        let mut points = daily(0, &[100; 14]);
+        // the scraper stops for ten days, then picks up where it left off
+        let (resumed_at, total) = *points.last().unwrap();
+        points.extend((0..=7).map(|day| {
+            (resumed_at + (10 + day) * MS_PER_DAY, total + day * 100)
+        }));
+
+        let anomalies =
+            detect_in_points(Source::Overall, &points, DEFAULT_GAP_FACTOR);
+        assert_eq!(anomalies, vec![]);
+    }
+}
@@ -0,0 +1,335 @@
+//! Period-over-period comparison of daily downloads, e.g. this month against
+//! last month, or the 30 days after one release against those after another.
+//!
+//! Both periods are lined up by day since their start, so they can be
+//! overlaid whatever their dates.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate};
+use chrono_tz::Tz;
+use fibermc_sdk::models::ModStatsResponse;
+
+use super::resample::{daily_downloads, day_start};
+use super::{sorted_points, Source};
+use crate::format::Locale;
+
+/// The most days a custom period may span.
+pub const MAX_PERIOD_DAYS: u32 = 366;
+
+/// A run of whole days, in the time zone they are compared in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Period {
+    pub start: NaiveDate,
+    pub days: u32,
+}
+
+impl Period {
+    /// The last day of the period. A period of no days is taken to end on
+    /// the day it starts.
+    pub fn last_day(&self) -> NaiveDate {
+        self.start + Days::new(self.days.saturating_sub(1) as u64)
+    }
+
+    /// The period's first and last days, written for `locale`.
+    pub fn describe(&self, locale: Locale) -> String {
+        format!(
+            "{} to {}",
+            locale.date(self.start),
+            locale.date(self.last_day())
+        )
+    }
+}
+
+/// Downloads over one [`Period`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeriodDownloads {
+    pub period: Period,
+    /// Downloads on each day of the period, `None` where there is no data.
+    pub daily: Vec<Option<i64>>,
+    /// Downloads over the days with data.
+    pub total: i64,
+    /// Mean downloads per day with data.
+    pub mean: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    pub source: Source,
+    pub current: PeriodDownloads,
+    pub previous: PeriodDownloads,
+    /// Percentage change of the mean daily downloads from the previous period
+    /// to the current one. Means rather than totals, so periods of different
+    /// lengths or with missing days compare fairly.
+    pub change: Option<f64>,
+}
+
+/// Ready-made pairs of periods, ending at the last full day of data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PeriodPreset {
+    /// The last 7 days against the 7 before.
+    Week,
+    /// The last 30 days against the 30 before.
+    ThirtyDays,
+    /// This calendar month so far against the whole of last month.
+    CalendarMonth,
+}
+
+impl PeriodPreset {
+    pub const ALL: [PeriodPreset; 3] = [
+        PeriodPreset::Week,
+        PeriodPreset::ThirtyDays,
+        PeriodPreset::CalendarMonth,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PeriodPreset::Week => "week",
+            PeriodPreset::ThirtyDays => "30-days",
+            PeriodPreset::CalendarMonth => "month",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PeriodPreset::Week => "Last 7 days vs the 7 before",
+            PeriodPreset::ThirtyDays => "Last 30 days vs the 30 before",
+            PeriodPreset::CalendarMonth => "This month vs last month",
+        }
+    }
+
+    /// The current and previous periods, for data whose last full day is
+    /// `last_day`.
+    pub fn periods(self, last_day: NaiveDate) -> (Period, Period) {
+        let trailing = |days: u32| {
+            let start = last_day - Days::new(days as u64 - 1);
+            let current = Period { start, days };
+            let previous = Period {
+                start: start - Days::new(days as u64),
+                days,
+            };
+            (current, previous)
+        };
+        match self {
+            PeriodPreset::Week => trailing(7),
+            PeriodPreset::ThirtyDays => trailing(30),
+            PeriodPreset::CalendarMonth => {
+                let start = last_day.with_day(1).unwrap();
+                let previous_start = start - Months::new(1);
+                let current = Period {
+                    start,
+                    days: last_day.day(),
+                };
+                let previous = Period {
+                    start: previous_start,
+                    days: (start - previous_start).num_days() as u32,
+                };
+                (current, previous)
+            }
+        }
+    }
+}
+
+impl fmt::Display for PeriodPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PeriodPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PeriodPreset::ALL
+            .into_iter()
+            .find(|preset| preset.as_str() == s)
+            .ok_or_else(|| format!("unknown comparison preset '{s}'"))
+    }
+}
+
+/// The last day in `time_zone` one series of `stats` fully covers.
+pub fn last_full_day(
+    stats: &ModStatsResponse,
+    source: Source,
+    time_zone: Tz,
+) -> Option<NaiveDate> {
+    last_full_day_in_points(&sorted_points(source.series(stats)), time_zone)
+}
+
+/// The last day in `time_zone` the time-ordered `points` fully cover. `None`
+/// if they don't cover a whole day.
+pub fn last_full_day_in_points(
+    points: &[(i64, i64)],
+    time_zone: Tz,
+) -> Option<NaiveDate> {
+    let (&(first_at, _), &(last_at, _)) = (points.first()?, points.last()?);
+    let last = DateTime::from_timestamp_millis(last_at)?;
+    let day = last.with_timezone(&time_zone).date_naive() - Days::new(1);
+    (day_start(day, time_zone).timestamp_millis() >= first_at).then_some(day)
+}
+
+/// Daily downloads of one series of `stats` over `current` and `previous`,
+/// days running midnight to midnight in `time_zone`.
+pub fn compare(
+    stats: &ModStatsResponse,
+    source: Source,
+    current: Period,
+    previous: Period,
+    time_zone: Tz,
+) -> Comparison {
+    let daily = daily_downloads(stats, source, time_zone);
+    let downloads_over = |period: Period| {
+        let days: Vec<Option<i64>> = period
+            .start
+            .iter_days()
+            .take(period.days as usize)
+            .map(|day| daily.get(&day).copied())
+            .collect();
+        let known: Vec<i64> = days.iter().flatten().copied().collect();
+        let total = known.iter().sum();
+        PeriodDownloads {
+            period,
+            daily: days,
+            total,
+            mean: (!known.is_empty())
+                .then(|| total as f64 / known.len() as f64),
+        }
+    };
+
+    let (current, previous) =
+        (downloads_over(current), downloads_over(previous));
+    let change = match (current.mean, previous.mean) {
+        (Some(now), Some(before)) if before > 0.0 => {
+            Some((now - before) / before * 100.0)
+        }
+        _ => None,
+    };
+    Comparison {
+        source,
+        current,
+        previous,
+        change,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::testing::at;
+
+    fn date(date: &str) -> NaiveDate {
+        date.parse().unwrap()
+    }
+
+    #[test]
+    fn last_day_of_a_period() {
+        let start = date("2024-02-28");
+        // (days, last day)
+        let cases = [
+            (0, "2024-02-28"),
+            (1, "2024-02-28"),
+            (2, "2024-02-29"),
+            (3, "2024-03-01"),
+        ];
+
+        for (days, last_day) in cases {
+            let period = Period { start, days };
+            assert_eq!(period.last_day(), date(last_day), "{days} days");
+        }
+    }
+
+    #[test]
+    fn preset_periods() {
+        let period = |start: &str, days| Period {
+            start: date(start),
+            days,
+        };
+        // (preset, last full day, current period, previous period)
+        let cases = [
+            (
+                PeriodPreset::Week,
+                "2024-03-10",
+                period("2024-03-04", 7),
+                period("2024-02-26", 7),
+            ),
+            (
+                PeriodPreset::ThirtyDays,
+                "2024-03-10",
+                period("2024-02-10", 30),
+                period("2024-01-11", 30),
+            ),
+            (
+                PeriodPreset::CalendarMonth,
+                "2024-03-10",
+                period("2024-03-01", 10),
+                period("2024-02-01", 29),
+            ),
+            (
+                PeriodPreset::CalendarMonth,
+                "2024-03-31",
+                period("2024-03-01", 31),
+                period("2024-02-01", 29),
+            ),
+            (
+                PeriodPreset::CalendarMonth,
+                "2024-01-01",
+                period("2024-01-01", 1),
+                period("2023-12-01", 31),
+            ),
+        ];
+
+        for (preset, last_day, current, previous) in cases {
+            assert_eq!(
+                preset.periods(date(last_day)),
+                (current, previous),
+                "{preset} to {last_day}"
+            );
+        }
+    }
+
+    #[test]
+    fn last_full_day_of_points() {
+        let new_york = Tz::America__New_York;
+        // (points, time zone, last full day)
+        let cases = [
+            (vec![], Tz::UTC, None),
+            (
+                vec!["2024-01-01T10:00:00Z", "2024-01-01T14:00:00Z"],
+                Tz::UTC,
+                None,
+            ),
+            (
+                vec!["2024-01-01T00:00:00Z", "2024-01-02T00:00:00Z"],
+                Tz::UTC,
+                Some("2024-01-01"),
+            ),
+            (
+                vec!["2024-01-01T00:00:01Z", "2024-01-02T00:00:00Z"],
+                Tz::UTC,
+                None,
+            ),
+            (
+                vec!["2024-01-01T00:00:00Z", "2024-01-05T23:59:59Z"],
+                Tz::UTC,
+                Some("2024-01-04"),
+            ),
+            // still the 4th in New York, so the 3rd is the last whole day
+            (
+                vec!["2024-01-01T00:00:00Z", "2024-01-05T03:00:00Z"],
+                new_york,
+                Some("2024-01-03"),
+            ),
+        ];
+
+        for (times, time_zone, last_day) in cases {
+            let points: Vec<(i64, i64)> =
+                times.iter().map(|time| (at(time), 0)).collect();
+            assert_eq!(
+                last_full_day_in_points(&points, time_zone),
+                last_day.map(date),
+                "{times:?} in {time_zone}"
+            );
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Detection of missing data in download series.
+//!
+//! The scraper normally samples at a steady rate, so an interval much longer
+//! than the series' typical (median) sampling interval means it wasn't
+//! running. Whatever happened in between is unknown, so charts should not
+//! draw such an interval as if it were measured.
+
+use fibermc_sdk::models::ModStatsResponse;
+use serde::Serialize;
+
+use super::{median, sorted_points, Source, MS_PER_DAY};
+
+/// How many times the typical sampling interval an interval has to span to
+/// count as a gap.
+pub const DEFAULT_GAP_FACTOR: f64 = 3.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Gap {
+    pub source: Source,
+    /// Unix time in milliseconds of the last point before the gap.
+    pub start: i64,
+    /// Unix time in milliseconds of the first point after the gap.
+    pub end: i64,
+    pub start_downloads: i64,
+    pub end_downloads: i64,
+    /// The series' typical sampling interval, in milliseconds.
+    pub typical_interval: i64,
+}
+
+impl Gap {
+    pub fn days(&self) -> f64 {
+        (self.end - self.start) as f64 / MS_PER_DAY as f64
+    }
+}
+
+/// Gaps in every series of `stats`, in time order.
+pub fn detect(stats: &ModStatsResponse, factor: f64) -> Vec<Gap> {
+    let mut gaps: Vec<Gap> = Source::ALL
+        .into_iter()
+        .flat_map(|source| {
+            detect_in_points(
+                source,
+                &sorted_points(source.series(stats)),
+                factor,
+            )
+        })
+        .collect();
+    gaps.sort_by_key(|gap| (gap.start, gap.end));
+    gaps
+}
+
+/// Gaps between the time-ordered `points` of one series, where an interval is
+/// over `factor` times the typical one.
+pub fn detect_in_points(
+    source: Source,
+    points: &[(i64, i64)],
+    factor: f64,
+) -> Vec<Gap> {
+    let Some(typical_interval) = typical_interval(points) else {
+        return Vec::new();
+    };
+    let limit = typical_interval as f64 * factor.max(1.0);
+
+    points
+        .windows(2)
+        .filter(|pair| (pair[1].0 - pair[0].0) as f64 > limit)
+        .map(|pair| Gap {
+            source,
+            start: pair[0].0,
+            end: pair[1].0,
+            start_downloads: pair[0].1,
+            end_downloads: pair[1].1,
+            typical_interval,
+        })
+        .collect()
+}
+
+/// The median spacing of time-ordered `points`, ignoring duplicates. `None`
+/// with fewer than two distinct times.
+pub fn typical_interval(points: &[(i64, i64)]) -> Option<i64> {
+    let mut intervals: Vec<f64> = points
+        .windows(2)
+        .map(|pair| (pair[1].0 - pair[0].0) as f64)
+        .filter(|interval| *interval > 0.0)
+        .collect();
+    median(&mut intervals).map(|interval| interval as i64)
+}
+
+/// Splits time-ordered `points` into the runs between `gaps`, which must be in
+/// time order.
+pub fn split_at_gaps(
+    points: &[(i64, i64)],
+    gaps: &[Gap],
+) -> Vec<Vec<(i64, i64)>> {
+    let mut segments = vec![Vec::new()];
+    let mut gaps = gaps.iter().peekable();
+    for &point in points {
+        if gaps.next_if(|gap| point.0 >= gap.end).is_some() {
+            segments.push(Vec::new());
+        }
+        segments.last_mut().unwrap().push(point);
+    }
+    segments.retain(|segment| !segment.is_empty());
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3_600_000;
+
+    /// Hourly points, with the interval after the fifth stretched to
+    /// `long_interval`.
+    fn hourly_with(long_interval: i64) -> Vec<(i64, i64)> {
+        let mut points: Vec<(i64, i64)> =
+            (0..5).map(|i| (i * HOUR, i * 10)).collect();
+        let resumed_at = 4 * HOUR + long_interval;
+        points.extend((0..5).map(|i| (resumed_at + i * HOUR, 100 + i * 10)));
+        points
+    }
+
+    #[test]
+    fn gaps_are_intervals_over_factor_times_the_typical() {
+        // (name, long interval, factor, whether it is a gap)
+        let cases = [
+            ("at the factor", 3 * HOUR, 3.0, false),
+            ("just over the factor", 3 * HOUR + 1, 3.0, true),
+            ("under a larger factor", 3 * HOUR + 1, 4.0, false),
+            ("factors below one count as one", HOUR, 0.5, false),
+            ("just over a factor below one", HOUR + 1, 0.5, true),
+        ];
+
+        for (name, long_interval, factor, is_gap) in cases {
+            let gaps = detect_in_points(
+                Source::Overall,
+                &hourly_with(long_interval),
+                factor,
+            );
+            let expected = is_gap.then(|| Gap {
+                source: Source::Overall,
+                start: 4 * HOUR,
+                end: 4 * HOUR + long_interval,
+                start_downloads: 40,
+                end_downloads: 100,
+                typical_interval: HOUR,
+            });
+            assert_eq!(gaps, Vec::from_iter(expected), "{name}");
+        }
+    }
+
+    #[test]
+    fn typical_interval_ignores_duplicate_times() {
+        let points = [(0, 0), (HOUR, 1), (HOUR, 2), (2 * HOUR, 3)];
+        assert_eq!(typical_interval(&points), Some(HOUR));
+        assert_eq!(typical_interval(&[(0, 0), (0, 1)]), None);
+        assert_eq!(typical_interval(&[(0, 0)]), None);
+        assert!(detect_in_points(Source::Overall, &[(0, 0)], 3.0).is_empty());
+    }
+
+    #[test]
+    fn splits_into_runs_between_gaps() {
+        let points = hourly_with(10 * HOUR);
+        let gaps = detect_in_points(Source::Overall, &points, 3.0);
+
+        assert_eq!(
+            split_at_gaps(&points, &gaps),
+            vec![points[..5].to_vec(), points[5..].to_vec()]
+        );
+        assert_eq!(split_at_gaps(&points, &[]), vec![points.clone()]);
+        assert_eq!(split_at_gaps(&[], &gaps), Vec::<Vec<(i64, i64)>>::new());
+    }
+}
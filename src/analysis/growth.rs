@@ -0,0 +1,252 @@
+//! Summary growth figures for a download series: recent downloads, average
+//! and best daily downloads, and period-over-period growth.
+//!
+//! Downloads over a period are read off the cumulative series by linear
+//! interpolation, so they don't depend on points falling exactly on period
+//! boundaries. Periods are measured back from the latest data point, so stale
+//! data still gives sensible figures.
+
+use chrono::DateTime;
+use chrono_tz::Tz;
+use fibermc_sdk::models::ModStatsResponse;
+
+use super::resample::Bucket;
+use super::{downloads_at, sorted_points, Source, MS_PER_DAY};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrowthStats {
+    pub source: Source,
+    /// Downloads in the 24 hours up to the latest data point.
+    pub last_day: Option<i64>,
+    /// Downloads in the 7 days up to the latest data point.
+    pub last_week: Option<i64>,
+    /// Downloads in the 30 days up to the latest data point.
+    pub last_month: Option<i64>,
+    /// Downloads per day across the whole series.
+    pub average_daily: Option<f64>,
+    /// Percentage change of the last 7 days' downloads over the 7 before.
+    pub week_over_week: Option<f64>,
+    /// Percentage change of the last 30 days' downloads over the 30 before.
+    pub month_over_month: Option<f64>,
+    /// Start (unix time in milliseconds) and downloads of the day, in the
+    /// time zone the figures were worked out in, with the most downloads,
+    /// among days the series fully covers.
+    pub best_day: Option<(i64, i64)>,
+    /// Whole days from the first data point to `now`.
+    pub days_tracked: i64,
+}
+
+/// Growth figures for every series of `stats` with at least two points, with
+/// days in `time_zone`.
+pub fn growth_stats(
+    stats: &ModStatsResponse,
+    now: i64,
+    time_zone: Tz,
+) -> Vec<GrowthStats> {
+    Source::ALL
+        .into_iter()
+        .filter_map(|source| {
+            let points = sorted_points(source.series(stats));
+            growth_in_points(source, &points, now, time_zone)
+        })
+        .collect()
+}
+
+/// Growth figures for the time-ordered `points` of one series, or `None` if
+/// they span no time.
+pub fn growth_in_points(
+    source: Source,
+    points: &[(i64, i64)],
+    now: i64,
+    time_zone: Tz,
+) -> Option<GrowthStats> {
+    let (&(first_at, first), &(last_at, last)) =
+        (points.first()?, points.last()?);
+    if last_at <= first_at {
+        return None;
+    }
+
+    // downloads in the `days` long period ending `periods_ago` such periods
+    // before the latest point
+    let downloads_in = |days: i64, periods_ago: i64| {
+        let end = last_at - periods_ago * days * MS_PER_DAY;
+        let start = end - days * MS_PER_DAY;
+        Some(downloads_at(points, end)? - downloads_at(points, start)?)
+    };
+    let growth = |days: i64| {
+        let (current, previous) =
+            (downloads_in(days, 0)?, downloads_in(days, 1)?);
+        (previous > 0.0).then(|| (current - previous) / previous * 100.0)
+    };
+
+    let tracked_days = (last_at - first_at) as f64 / MS_PER_DAY as f64;
+
+    Some(GrowthStats {
+        source,
+        last_day: downloads_in(1, 0).map(|d| d.round() as i64),
+        last_week: downloads_in(7, 0).map(|d| d.round() as i64),
+        last_month: downloads_in(30, 0).map(|d| d.round() as i64),
+        average_daily: (tracked_days >= 1.0)
+            .then(|| (last - first) as f64 / tracked_days),
+        week_over_week: growth(7),
+        month_over_month: growth(30),
+        best_day: best_day(points, time_zone),
+        days_tracked: (now - first_at).max(0) / MS_PER_DAY,
+    })
+}
+
+/// The day in `time_zone` with the most downloads, of those entirely within
+/// the series.
+fn best_day(points: &[(i64, i64)], time_zone: Tz) -> Option<(i64, i64)> {
+    let (first_at, last_at) = (points.first()?.0, points.last()?.0);
+
+    // the first whole day starts at the first midnight at or after the first
+    // point
+    let first = DateTime::from_timestamp_millis(first_at)?;
+    let mut day_start = Bucket::Day.floor(first, time_zone);
+    if day_start.timestamp_millis() < first_at {
+        day_start = Bucket::Day.next(day_start, time_zone);
+    }
+
+    let mut best: Option<(i64, i64)> = None;
+    loop {
+        // not always 24 hours, across a daylight saving change
+        let day_end = Bucket::Day.next(day_start, time_zone);
+        let (start, end) =
+            (day_start.timestamp_millis(), day_end.timestamp_millis());
+        if end > last_at {
+            break;
+        }
+        let downloads =
+            downloads_at(points, end)? - downloads_at(points, start)?;
+        let downloads = downloads.round() as i64;
+        if !matches!(best, Some((_, most)) if most >= downloads) {
+            best = Some((start, downloads));
+        }
+        day_start = day_end;
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::testing::{at, daily};
+
+    const NEW_YORK: Tz = Tz::America__New_York;
+
+    fn growth(points: &[(i64, i64)], time_zone: Tz) -> GrowthStats {
+        let now = points.last().unwrap().0;
+        growth_in_points(Source::Overall, points, now, time_zone).unwrap()
+    }
+
+    #[test]
+    fn steady_growth() {
+        let start = at("2024-01-01T00:00:00Z");
+        let points = daily(start, &[100; 70]);
+        let now = points.last().unwrap().0 + MS_PER_DAY / 2;
+
+        let growth =
+            growth_in_points(Source::Overall, &points, now, Tz::UTC).unwrap();
+        assert_eq!(
+            growth,
+            GrowthStats {
+                source: Source::Overall,
+                last_day: Some(100),
+                last_week: Some(700),
+                last_month: Some(3000),
+                average_daily: Some(100.0),
+                week_over_week: Some(0.0),
+                month_over_month: Some(0.0),
+                // the earliest of the equally good days
+                best_day: Some((start, 100)),
+                days_tracked: 70,
+            }
+        );
+    }
+
+    #[test]
+    fn periods_need_data_on_both_sides() {
+        let start = at("2024-01-01T00:00:00Z");
+        // (name, daily downloads, last week, week over week)
+        let cases = [
+            ("shorter than a week", vec![100; 3], None, None),
+            ("exactly one week", vec![100; 7], Some(700), None),
+            (
+                "doubling",
+                [vec![100; 7], vec![200; 7]].concat(),
+                Some(1400),
+                Some(100.0),
+            ),
+            (
+                "nothing the week before",
+                [vec![0; 7], vec![100; 7]].concat(),
+                Some(700),
+                None,
+            ),
+        ];
+
+        for (name, downloads, last_week, week_over_week) in cases {
+            let growth = growth(&daily(start, &downloads), Tz::UTC);
+            assert_eq!(growth.last_week, last_week, "{name}");
+            assert_eq!(growth.week_over_week, week_over_week, "{name}");
+        }
+    }
+
+    #[test]
+    fn interpolates_between_sparse_points() {
+        let start = at("2024-01-01T00:00:00Z");
+        let points: Vec<(i64, i64)> = (0..=10)
+            .map(|i| (start + i * 2 * MS_PER_DAY, i * 200))
+            .collect();
+
+        let growth = growth(&points, Tz::UTC);
+        assert_eq!(growth.last_day, Some(100));
+        assert_eq!(growth.last_week, Some(700));
+    }
+
+    #[test]
+    fn best_day_follows_the_time_zone() {
+        let start = at("2024-01-01T00:00:00Z");
+        let points = daily(start, &[100, 100, 100, 500, 100, 100, 100]);
+
+        assert_eq!(
+            growth(&points, Tz::UTC).best_day,
+            Some((at("2024-01-04T00:00:00Z"), 500))
+        );
+        // New York's Jan 4 runs from 05:00 UTC, so takes 19 hours of the
+        // busy UTC day and 5 of the next
+        assert_eq!(
+            growth(&points, NEW_YORK).best_day,
+            Some((at("2024-01-04T05:00:00Z"), 417))
+        );
+    }
+
+    #[test]
+    fn days_tracked_counts_whole_days_to_now() {
+        let start = at("2024-01-01T00:00:00Z");
+        let points = daily(start, &[100; 3]);
+        let days_tracked = |now| {
+            growth_in_points(Source::Overall, &points, now, Tz::UTC)
+                .unwrap()
+                .days_tracked
+        };
+
+        assert_eq!(days_tracked(start - MS_PER_DAY), 0);
+        assert_eq!(days_tracked(start + MS_PER_DAY - 1), 0);
+        assert_eq!(days_tracked(start + 10 * MS_PER_DAY), 10);
+    }
+
+    #[test]
+    fn needs_points_spanning_time() {
+        let start = at("2024-01-01T00:00:00Z");
+        for points in
+            [vec![], vec![(start, 10)], vec![(start, 10), (start, 20)]]
+        {
+            assert_eq!(
+                growth_in_points(Source::Overall, &points, start, Tz::UTC),
+                None
+            );
+        }
+    }
+}
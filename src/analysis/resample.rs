@@ -0,0 +1,432 @@
+//! Resampling of download series onto a regular time grid.
+//!
+//! Each source is sampled at its own irregular times, so series can't be
+//! compared or differenced point by point. Resampling reads each series'
+//! cumulative downloads at the same bucket boundaries (hours, days,
+//! Monday-starting weeks or months), from the first boundary at or after the
+//! series' first point to the last boundary at or before its last point.
+//! Boundaries inside a [`Gap`] are left out rather than filled, as nothing is
+//! known about them.
+//!
+//! Buckets follow the wall clock of a time zone, so a day runs from one local
+//! midnight to the next and is 23 or 25 hours long across a daylight saving
+//! change.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{
+    DateTime, Datelike, Days, Duration, LocalResult, Months, NaiveDate,
+    NaiveDateTime, NaiveTime, Offset, TimeZone, Timelike, Utc,
+};
+use chrono_tz::Tz;
+use fibermc_sdk::models::ModStatsResponse;
+use serde::{Deserialize, Serialize};
+
+use super::gaps::{self, Gap, DEFAULT_GAP_FACTOR};
+use super::{downloads_at, sorted_points, Source};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Bucket {
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+impl Bucket {
+    pub const ALL: [Bucket; 4] =
+        [Bucket::Hour, Bucket::Day, Bucket::Week, Bucket::Month];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Bucket::Hour => "hour",
+            Bucket::Day => "day",
+            Bucket::Week => "week",
+            Bucket::Month => "month",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Bucket::Hour => "Hourly",
+            Bucket::Day => "Daily",
+            Bucket::Week => "Weekly",
+            Bucket::Month => "Monthly",
+        }
+    }
+
+    /// The start of the bucket containing `time`, on the wall clock of
+    /// `time_zone`.
+    pub fn floor(self, time: DateTime<Utc>, time_zone: Tz) -> DateTime<Utc> {
+        let local = time.with_timezone(&time_zone).naive_local();
+        let date = local.date();
+        let start = match self {
+            Bucket::Hour => date.and_hms_opt(local.hour(), 0, 0).unwrap(),
+            Bucket::Day => date.and_time(NaiveTime::MIN),
+            Bucket::Week => (date
+                - Days::new(date.weekday().num_days_from_monday() as u64))
+            .and_time(NaiveTime::MIN),
+            Bucket::Month => date.with_day(1).unwrap().and_time(NaiveTime::MIN),
+        };
+        local_start(start, time_zone)
+    }
+
+    /// The start of the bucket after the one starting at `start`, on the wall
+    /// clock of `time_zone`.
+    pub fn next(self, start: DateTime<Utc>, time_zone: Tz) -> DateTime<Utc> {
+        let date = start.with_timezone(&time_zone).date_naive();
+        let next = match self {
+            Bucket::Hour => return start + Duration::hours(1),
+            Bucket::Day => date + Days::new(1),
+            Bucket::Week => date + Days::new(7),
+            Bucket::Month => date + Months::new(1),
+        };
+        day_start(next, time_zone)
+    }
+}
+
+impl fmt::Display for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Bucket {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Bucket::ALL
+            .into_iter()
+            .find(|bucket| bucket.as_str() == s)
+            .ok_or_else(|| {
+                format!("unknown bucket size '{s}', expected hour, day, week or month")
+            })
+    }
+}
+
+/// The moment `date` starts in `time_zone`: usually its midnight, or later
+/// where the clocks go forward over midnight.
+pub fn day_start(date: NaiveDate, time_zone: Tz) -> DateTime<Utc> {
+    local_start(date.and_time(NaiveTime::MIN), time_zone)
+}
+
+/// The first moment the wall clock of `time_zone` reads `local`: the earlier
+/// of a time repeated when the clocks go back, or the moment the clocks go
+/// forward past a skipped one.
+fn local_start(local: NaiveDateTime, time_zone: Tz) -> DateTime<Utc> {
+    match time_zone.from_local_datetime(&local) {
+        LocalResult::Single(t) | LocalResult::Ambiguous(t, _) => {
+            t.with_timezone(&Utc)
+        }
+        LocalResult::None => {
+            // read with the offset from before the change
+            let before = time_zone
+                .offset_from_utc_datetime(&(local - Duration::days(1)))
+                .fix();
+            (local - before).and_utc()
+        }
+    }
+}
+
+/// How a boundary that falls between two points gets its value.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub enum Fill {
+    /// Linearly interpolated between the points either side.
+    #[default]
+    Interpolate,
+    /// The value of the latest point at or before the boundary, as a counter
+    /// only known to have reached that value by then.
+    CarryForward,
+}
+
+impl Fill {
+    pub const ALL: [Fill; 2] = [Fill::Interpolate, Fill::CarryForward];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Fill::Interpolate => "interpolate",
+            Fill::CarryForward => "carry-forward",
+        }
+    }
+}
+
+impl fmt::Display for Fill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Fill {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Fill::ALL
+            .into_iter()
+            .find(|fill| fill.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown fill '{s}', expected interpolate or carry-forward"
+                )
+            })
+    }
+}
+
+/// Cumulative downloads at each `bucket` boundary in `time_zone` within the
+/// time-ordered `points`, as `(unix time in milliseconds, downloads)`,
+/// skipping boundaries strictly inside any of the series' `gaps`.
+pub fn resample(
+    points: &[(i64, i64)],
+    bucket: Bucket,
+    fill: Fill,
+    gaps: &[Gap],
+    time_zone: Tz,
+) -> Vec<(i64, i64)> {
+    let (Some(&(first_at, _)), Some(&(last_at, _))) =
+        (points.first(), points.last())
+    else {
+        return Vec::new();
+    };
+    let Some(first) = DateTime::from_timestamp_millis(first_at) else {
+        return Vec::new();
+    };
+
+    let mut boundary = bucket.floor(first, time_zone);
+    if boundary.timestamp_millis() < first_at {
+        boundary = bucket.next(boundary, time_zone);
+    }
+
+    let mut resampled = Vec::new();
+    while boundary.timestamp_millis() <= last_at {
+        let t = boundary.timestamp_millis();
+        boundary = bucket.next(boundary, time_zone);
+        if gaps.iter().any(|gap| gap.start < t && t < gap.end) {
+            continue;
+        }
+        let downloads = match fill {
+            Fill::Interpolate => {
+                downloads_at(points, t).map(|d| d.round() as i64)
+            }
+            Fill::CarryForward => points
+                [..points.partition_point(|&(at, _)| at <= t)]
+                .last()
+                .map(|&(_, d)| d),
+        };
+        if let Some(downloads) = downloads {
+            resampled.push((t, downloads));
+        }
+    }
+    resampled
+}
+
+/// [`resample`] for one series of `stats`, leaving out the gaps found at
+/// `gap_factor`.
+pub fn resample_source(
+    stats: &ModStatsResponse,
+    source: Source,
+    bucket: Bucket,
+    fill: Fill,
+    gap_factor: f64,
+    time_zone: Tz,
+) -> Vec<(i64, i64)> {
+    let points = sorted_points(source.series(stats));
+    let gaps = gaps::detect_in_points(source, &points, gap_factor);
+    resample(&points, bucket, fill, &gaps, time_zone)
+}
+
+/// Downloads within each bucket of a [`resample`]d series, as `(bucket start,
+/// downloads)`. Buckets missing either boundary, e.g. next to a gap, are left
+/// out.
+pub fn bucket_deltas(
+    resampled: &[(i64, i64)],
+    bucket: Bucket,
+    time_zone: Tz,
+) -> Vec<(i64, i64)> {
+    resampled
+        .windows(2)
+        .filter(|pair| {
+            DateTime::from_timestamp_millis(pair[0].0).is_some_and(|start| {
+                bucket.next(start, time_zone).timestamp_millis() == pair[1].0
+            })
+        })
+        .map(|pair| (pair[0].0, pair[1].1 - pair[0].1))
+        .collect()
+}
+
+/// Downloads on each day in `time_zone` one series of `stats` fully covers,
+/// leaving out days in gaps.
+pub fn daily_downloads(
+    stats: &ModStatsResponse,
+    source: Source,
+    time_zone: Tz,
+) -> HashMap<NaiveDate, i64> {
+    let points = sorted_points(source.series(stats));
+    let gaps = gaps::detect_in_points(source, &points, DEFAULT_GAP_FACTOR);
+    let daily =
+        resample(&points, Bucket::Day, Fill::Interpolate, &gaps, time_zone);
+    bucket_deltas(&daily, Bucket::Day, time_zone)
+        .into_iter()
+        .filter_map(|(t, downloads)| {
+            let start = DateTime::from_timestamp_millis(t)?;
+            Some((start.with_timezone(&time_zone).date_naive(), downloads))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::testing::{at, daily};
+    use crate::analysis::MS_PER_DAY;
+
+    const NEW_YORK: Tz = Tz::America__New_York;
+
+    fn utc(timestamp: &str) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(at(timestamp)).unwrap()
+    }
+
+    #[test]
+    fn buckets_follow_the_wall_clock() {
+        // (bucket, time zone, time, start of its bucket, start of the next)
+        let cases = [
+            (
+                Bucket::Hour,
+                Tz::UTC,
+                "2024-05-15T10:30:00Z",
+                "2024-05-15T10:00:00Z",
+                "2024-05-15T11:00:00Z",
+            ),
+            (
+                Bucket::Day,
+                Tz::UTC,
+                "2024-05-15T10:30:00Z",
+                "2024-05-15T00:00:00Z",
+                "2024-05-16T00:00:00Z",
+            ),
+            (
+                Bucket::Week,
+                Tz::UTC,
+                "2024-05-15T10:30:00Z",
+                "2024-05-13T00:00:00Z",
+                "2024-05-20T00:00:00Z",
+            ),
+            (
+                Bucket::Month,
+                Tz::UTC,
+                "2024-02-15T10:30:00Z",
+                "2024-02-01T00:00:00Z",
+                "2024-03-01T00:00:00Z",
+            ),
+            // still the 14th in New York
+            (
+                Bucket::Day,
+                NEW_YORK,
+                "2024-05-15T02:00:00Z",
+                "2024-05-14T04:00:00Z",
+                "2024-05-15T04:00:00Z",
+            ),
+            // the clocks go forward, so the 10th is 23 hours long
+            (
+                Bucket::Day,
+                NEW_YORK,
+                "2024-03-10T12:00:00Z",
+                "2024-03-10T05:00:00Z",
+                "2024-03-11T04:00:00Z",
+            ),
+            // and back, so the 3rd is 25 hours long
+            (
+                Bucket::Day,
+                NEW_YORK,
+                "2024-11-03T12:00:00Z",
+                "2024-11-03T04:00:00Z",
+                "2024-11-04T05:00:00Z",
+            ),
+        ];
+
+        for (bucket, time_zone, time, start, next) in cases {
+            let floor = bucket.floor(utc(time), time_zone);
+            assert_eq!(floor, utc(start), "{bucket} containing {time}");
+            assert_eq!(
+                bucket.next(floor, time_zone),
+                utc(next),
+                "{bucket} after {start}"
+            );
+        }
+    }
+
+    #[test]
+    fn boundaries_lie_within_the_points() {
+        let points = [
+            (at("2024-01-01T06:00:00Z"), 0),
+            (at("2024-01-03T00:00:00Z"), 420),
+            (at("2024-01-04T06:00:00Z"), 720),
+        ];
+
+        assert_eq!(
+            resample(&points, Bucket::Day, Fill::Interpolate, &[], Tz::UTC),
+            vec![
+                (at("2024-01-02T00:00:00Z"), 180),
+                (at("2024-01-03T00:00:00Z"), 420),
+                (at("2024-01-04T00:00:00Z"), 660),
+            ]
+        );
+        assert_eq!(
+            resample(&points, Bucket::Day, Fill::CarryForward, &[], Tz::UTC),
+            vec![
+                (at("2024-01-02T00:00:00Z"), 0),
+                (at("2024-01-03T00:00:00Z"), 420),
+                (at("2024-01-04T00:00:00Z"), 420),
+            ]
+        );
+        assert_eq!(
+            resample(&[], Bucket::Day, Fill::Interpolate, &[], Tz::UTC),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn skips_boundaries_inside_gaps() {
+        let start = at("2024-01-01T00:00:00Z");
+        let points = [
+            (start, 0),
+            (start + MS_PER_DAY, 100),
+            (start + 4 * MS_PER_DAY, 400),
+            (start + 5 * MS_PER_DAY, 500),
+        ];
+        let gaps = gaps::detect_in_points(Source::Overall, &points, 2.0);
+
+        let days: Vec<i64> =
+            resample(&points, Bucket::Day, Fill::Interpolate, &gaps, Tz::UTC)
+                .into_iter()
+                .map(|(t, _)| (t - start) / MS_PER_DAY)
+                .collect();
+        // the gap's own ends are measured, so are kept
+        assert_eq!(days, vec![0, 1, 4, 5]);
+    }
+
+    #[test]
+    fn bucket_deltas_across_month_ends() {
+        // 10 downloads a day through a leap year's February
+        let points = daily(at("2024-01-01T00:00:00Z"), &[10; 91]);
+        let monthly =
+            resample(&points, Bucket::Month, Fill::Interpolate, &[], Tz::UTC);
+
+        assert_eq!(
+            bucket_deltas(&monthly, Bucket::Month, Tz::UTC),
+            vec![
+                (at("2024-01-01T00:00:00Z"), 310),
+                (at("2024-02-01T00:00:00Z"), 290),
+                (at("2024-03-01T00:00:00Z"), 310),
+            ]
+        );
+        // buckets missing a boundary are left out
+        let without_february = [monthly[0], monthly[2], monthly[3]];
+        assert_eq!(
+            bucket_deltas(&without_february, Bucket::Month, Tz::UTC),
+            vec![(at("2024-03-01T00:00:00Z"), 310)]
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! The ways a download series can be shown: as running totals, as downloads
+//! per interval, or as growth relative to the first point.
+//!
+//! Transforms work on a series already split into runs of continuous data,
+//! so no interval ever spans a gap.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::format::Locale;
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+pub enum DisplayMode {
+    /// Total downloads so far, as reported.
+    #[default]
+    Cumulative,
+    /// Downloads since the previous point, e.g. per day when resampled to
+    /// days.
+    Incremental,
+    /// Total downloads as a percentage of the first point's.
+    Indexed,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 3] = [
+        DisplayMode::Cumulative,
+        DisplayMode::Incremental,
+        DisplayMode::Indexed,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DisplayMode::Cumulative => "cumulative",
+            DisplayMode::Incremental => "incremental",
+            DisplayMode::Indexed => "indexed",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DisplayMode::Cumulative => "Total downloads",
+            DisplayMode::Incremental => "New downloads",
+            DisplayMode::Indexed => "Growth (first point = 100%)",
+        }
+    }
+
+    pub fn caption(self) -> &'static str {
+        match self {
+            DisplayMode::Cumulative => "Downloads Over Time",
+            DisplayMode::Incremental => "New Downloads Over Time",
+            DisplayMode::Indexed => "Download Growth Over Time",
+        }
+    }
+
+    /// A value in this mode for an axis label, written compactly for
+    /// `locale`, e.g. `1.5M`, `+250` or `120%`.
+    pub fn axis_label(self, value: f64, locale: Locale) -> String {
+        match self {
+            DisplayMode::Cumulative => locale.compact(value),
+            DisplayMode::Incremental => {
+                let label = locale.compact(value);
+                match value.round() > 0.0 {
+                    true => format!("+{label}"),
+                    false => label,
+                }
+            }
+            DisplayMode::Indexed => locale.percent(value, 0),
+        }
+    }
+
+    /// A single point's value in this mode as a phrase, e.g. for a screen
+    /// reader: `1,200 downloads`, `+35 downloads` or `120.0% of the first
+    /// point`.
+    pub fn describe_value(self, value: f64, locale: Locale) -> String {
+        match self {
+            DisplayMode::Indexed => format!(
+                "{} of the first point",
+                self.point_label(value, locale)
+            ),
+            _ => format!("{} downloads", self.point_label(value, locale)),
+        }
+    }
+
+    /// A single point's value in this mode, in full for `locale`, e.g. for a
+    /// tooltip.
+    pub fn point_label(self, value: f64, locale: Locale) -> String {
+        match self {
+            DisplayMode::Cumulative => locale.decimal(value, 0),
+            DisplayMode::Incremental => locale.signed(value, 0),
+            DisplayMode::Indexed => locale.percent(value, 1),
+        }
+    }
+}
+
+impl fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DisplayMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DisplayMode::ALL
+            .into_iter()
+            .find(|mode| mode.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown display mode '{s}', expected cumulative, \
+                     incremental or indexed"
+                )
+            })
+    }
+}
+
+/// The runs of `(unix time in milliseconds, downloads)` points of one series,
+/// in time order, as values in `mode`.
+///
+/// Incremental values are differences from the previous point of the same
+/// run, so each run's first point is dropped. Indexed values are relative to
+/// the first point with any downloads; points before it are dropped.
+pub fn transform(
+    segments: &[Vec<(i64, i64)>],
+    mode: DisplayMode,
+) -> Vec<Vec<(i64, f64)>> {
+    let base = segments
+        .iter()
+        .flatten()
+        .map(|&(_, downloads)| downloads)
+        .find(|downloads| *downloads > 0);
+
+    segments
+        .iter()
+        .map(|segment| match mode {
+            DisplayMode::Cumulative => segment
+                .iter()
+                .map(|&(t, downloads)| (t, downloads as f64))
+                .collect(),
+            DisplayMode::Incremental => segment
+                .windows(2)
+                .map(|pair| (pair[1].0, (pair[1].1 - pair[0].1) as f64))
+                .collect(),
+            DisplayMode::Indexed => {
+                let Some(base) = base else {
+                    return Vec::new();
+                };
+                segment
+                    .iter()
+                    .skip_while(|&&(_, downloads)| downloads <= 0)
+                    .map(|&(t, downloads)| {
+                        (t, downloads as f64 / base as f64 * 100.0)
+                    })
+                    .collect()
+            }
+        })
+        .filter(|segment: &Vec<(i64, f64)>| !segment.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::gaps::{self, split_at_gaps};
+    use crate::analysis::testing::daily;
+    use crate::analysis::{Source, MS_PER_DAY};
+
+    #[test]
+    fn transforms_each_run() {
+        let runs = vec![
+            vec![(0, 10), (1, 15), (2, 25)],
+            vec![(10, 40), (11, 42)],
+            vec![(20, 50)],
+        ];
+        let leading_zeros = vec![vec![(0, 0), (1, 0)], vec![(5, 50), (6, 75)]];
+
+        let cases = [
+            (
+                "cumulative keeps every point",
+                runs.clone(),
+                DisplayMode::Cumulative,
+                vec![
+                    vec![(0, 10.0), (1, 15.0), (2, 25.0)],
+                    vec![(10, 40.0), (11, 42.0)],
+                    vec![(20, 50.0)],
+                ],
+            ),
+            (
+                "incremental drops each run's first point",
+                runs.clone(),
+                DisplayMode::Incremental,
+                vec![vec![(1, 5.0), (2, 10.0)], vec![(11, 2.0)]],
+            ),
+            (
+                "indexed is relative to the first point",
+                runs,
+                DisplayMode::Indexed,
+                vec![
+                    vec![(0, 100.0), (1, 150.0), (2, 250.0)],
+                    vec![(10, 400.0), (11, 420.0)],
+                    vec![(20, 500.0)],
+                ],
+            ),
+            (
+                "indexed skips leading zero downloads",
+                vec![vec![(0, 0), (1, 0), (2, 50), (3, 100)]],
+                DisplayMode::Indexed,
+                vec![vec![(2, 100.0), (3, 200.0)]],
+            ),
+            (
+                "indexed drops runs of only zero downloads",
+                leading_zeros.clone(),
+                DisplayMode::Indexed,
+                vec![vec![(5, 100.0), (6, 150.0)]],
+            ),
+            (
+                "incremental keeps runs of zero downloads",
+                leading_zeros,
+                DisplayMode::Incremental,
+                vec![vec![(1, 0.0)], vec![(6, 25.0)]],
+            ),
+            (
+                "indexed needs some downloads",
+                vec![vec![(0, 0), (1, 0)], vec![(5, 0)]],
+                DisplayMode::Indexed,
+                vec![],
+            ),
+            ("no runs", vec![], DisplayMode::Cumulative, vec![]),
+        ];
+
+        for (name, segments, mode, expected) in cases {
+            assert_eq!(transform(&segments, mode), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn no_interval_spans_a_gap() {
+        let mut points = daily(0, &[10, 20, 30, 40]);
+        points.push((20 * MS_PER_DAY, 1000));
+        points.push((21 * MS_PER_DAY, 1050));
+        let gaps = gaps::detect_in_points(Source::Overall, &points, 3.0);
+        let segments = split_at_gaps(&points, &gaps);
+
+        assert_eq!(
+            transform(&segments, DisplayMode::Incremental),
+            vec![
+                vec![
+                    (MS_PER_DAY, 10.0),
+                    (2 * MS_PER_DAY, 20.0),
+                    (3 * MS_PER_DAY, 30.0),
+                    (4 * MS_PER_DAY, 40.0),
+                ],
+                vec![(21 * MS_PER_DAY, 50.0)],
+            ]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Dated events marked on the downloads chart: Minecraft releases, which
+//! apply to every mod, and a mod's own releases or promotions, which come from
+//! the server's annotations file or a page's `annotate` query parameter.
+
+use chrono::NaiveDate;
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+
+/// Name of the query parameter holding ad-hoc annotations, as
+/// `DATE:LABEL` items separated by commas, e.g.
+/// `?annotate=2024-05-01:v2.0,2024-06-10:Promo`.
+pub const QUERY_PARAM: &str = "annotate";
+
+/// Release dates of Minecraft's major versions, in order.
+const MINECRAFT_RELEASES: &[(NaiveDate, &str)] = &[
+    (date(2019, 4, 23), "1.14"),
+    (date(2019, 12, 10), "1.15"),
+    (date(2020, 6, 23), "1.16"),
+    (date(2021, 6, 8), "1.17"),
+    (date(2021, 11, 30), "1.18"),
+    (date(2022, 6, 7), "1.19"),
+    (date(2023, 6, 7), "1.20"),
+    (date(2024, 6, 13), "1.21"),
+    (date(2024, 10, 22), "1.21.2"),
+    (date(2024, 12, 3), "1.21.4"),
+    (date(2025, 3, 25), "1.21.5"),
+    (date(2025, 6, 17), "1.21.6"),
+];
+
+/// A date for a const table, failing the build if it doesn't exist.
+const fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    /// A mod's own event, such as a release or promotion.
+    #[default]
+    Event,
+    MinecraftRelease,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub date: NaiveDate,
+    pub label: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub kind: AnnotationKind,
+}
+
+/// An annotation for every Minecraft release.
+pub fn minecraft_releases() -> Vec<Annotation> {
+    MINECRAFT_RELEASES
+        .iter()
+        .map(|&(date, version)| Annotation {
+            date,
+            label: version.to_string(),
+            description: Some(format!("Minecraft {version} released")),
+            kind: AnnotationKind::MinecraftRelease,
+        })
+        .collect()
+}
+
+/// Parses the value of the [`QUERY_PARAM`] query parameter, skipping items
+/// that aren't `DATE:LABEL`.
+pub fn parse_query(value: &str) -> Vec<Annotation> {
+    value
+        .split(',')
+        .filter_map(|item| {
+            let (date, label) = item.split_once(':')?;
+            let label = label.trim();
+            if label.is_empty() {
+                return None;
+            }
+            Some(Annotation {
+                date: date.trim().parse().ok()?,
+                label: label.to_string(),
+                description: None,
+                kind: AnnotationKind::Event,
+            })
+        })
+        .collect()
+}
+
+/// A mod's annotations from the server's annotations file, including the
+/// ones it applies to every mod.
+#[server]
+pub async fn mod_annotations(
+    mod_id: String,
+) -> Result<Vec<Annotation>, ServerFnError> {
+    Ok(crate::server::annotations::for_mod(&mod_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minecraft_releases_are_in_order() {
+        let releases = minecraft_releases();
+        assert_eq!(releases.len(), MINECRAFT_RELEASES.len());
+        assert!(releases.windows(2).all(|pair| pair[0].date < pair[1].date));
+        assert_eq!(releases[0].date, date(2019, 4, 23));
+        assert_eq!(releases[0].label, "1.14");
+    }
+
+    #[test]
+    fn parses_query_annotations() {
+        let parsed = parse_query("2024-05-01:v2.0, 2024-13-01:Bad,nolabel,:x");
+        assert_eq!(
+            parsed,
+            vec![Annotation {
+                date: date(2024, 5, 1),
+                label: "v2.0".to_string(),
+                description: None,
+                kind: AnnotationKind::Event,
+            }]
+        );
+    }
+}
@@ -1,5 +1,5 @@
+use leptos::config::LeptosOptions;
 use leptos::error::ErrorBoundary;
-use leptos::logging::log;
 use leptos::prelude::{
     signal, ClassAttribute, CollectView, ElementChild, Get, GlobalAttributes,
     OnAttribute, Update, *,
@@ -10,15 +10,39 @@ use leptos_router::components::{Route, Router, Routes};
 use leptos_router::hooks::use_params;
 use leptos_router::*;
 
+use crate::site;
 use crate::stats_page::{StatsPage, StatsPageParams};
 
+/// The HTML document pages are rendered into on the server.
+pub fn shell(options: LeptosOptions) -> impl IntoView {
+    view! {
+        <!DOCTYPE html>
+        <html lang="en">
+            <head>
+                <meta charset="utf-8"/>
+                <meta
+                    name="viewport"
+                    content="width=device-width, initial-scale=1"
+                />
+                {site::is_static_export().then(|| view! {
+                    <meta name=site::STATIC_EXPORT_META content="true"/>
+                })}
+                <AutoReload options=options.clone()/>
+                <HydrationScripts options/>
+                <MetaTags/>
+            </head>
+            <body>
+                <App/>
+            </body>
+        </html>
+    }
+}
+
 #[component]
 pub fn App() -> impl IntoView {
     // Provides context that manages stylesheets, titles, meta tags, etc.
     provide_meta_context();
 
-    logging::log!("where do I run?");
-
     view! {
         <Style media="screen" id="palette_light_dark" />
         <Style>
@@ -85,7 +109,9 @@ pub fn App() -> impl IntoView {
                         >
                             <Routes fallback=|| "Not found">
                                 <Route path=path!("") view=|| view! {<HomePage/>}/>
-                                <Route path=path!("stats/:mod_id") view=move || {
+                                // wait for the mod data before sending anything, so
+                                // crawlers see the per-mod title and preview tags
+                                <Route path=path!("stats/:mod_id") ssr=SsrMode::Async view=move || {
                                     let params = use_params::<StatsPageParams>();
                                     view! { <StatsPage params=params/> }
                                 }/>
@@ -105,13 +131,13 @@ fn HomePage() -> impl IntoView {
     let (count, set_count) = signal(0);
     let on_click = move |_| {
         set_count.update(|count| *count += 1);
-        log!("Clicked {} times", count.get());
+        tracing::debug!(count = count.get(), "clicked");
     };
 
     view! {
         <div>
             <h1>"Welcome to Leptos!"</h1>
-        <input type="text" on:keydown=|_| log!("key") on:change=|_| log!("change")/>
+        <input type="text" on:keydown=|_| tracing::trace!("key") on:change=|_| tracing::trace!("change")/>
         <button on:click=on_click>Click Me: {move || count.get()}</button>
         </div>
         <a href="./stats">"View mod stats"</a>
@@ -0,0 +1,872 @@
+//! Rendering of download stats to SVG charts with plotters.
+//!
+//! This has no DOM dependencies, so the same charts can be drawn in the
+//! browser and offline by the server binary.
+
+pub mod compare;
+pub mod heatmap;
+pub mod navigator;
+pub mod palette;
+
+use std::fmt;
+use std::io::Cursor;
+use std::ops::Range;
+use std::str::FromStr;
+
+use chrono::*;
+use chrono_tz::Tz;
+use fibermc_sdk::models::{ModStatsResponse, TimestampedModStats};
+use palette::ChartPalette;
+use plotters::prelude::*;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::Writer;
+
+use crate::analysis::anomalies::{self, Anomaly, AnomalyKind};
+use crate::analysis::gaps::{self, split_at_gaps, Gap, DEFAULT_GAP_FACTOR};
+use crate::analysis::resample::{day_start, resample, Bucket, Fill};
+use crate::analysis::transform::{transform, DisplayMode};
+use crate::analysis::{sorted_points, Source};
+use crate::annotations::{Annotation, AnnotationKind};
+use crate::format::Locale;
+use crate::milestones::{download_range, milestones, scaled_thresholds};
+
+/// Milestone markers aimed for on a chart, whatever the mod's scale.
+const MILESTONE_MARKERS: usize = 5;
+
+/// A labelled vertical rule drawn over the plot at time `x`, with a
+/// `description` shown when it is hovered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartMarker {
+    pub x: i64,
+    pub label: String,
+    pub description: String,
+    pub kind: MarkerKind,
+}
+
+/// What a [`ChartMarker`] marks, which sets its colour and CSS class.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkerKind {
+    Milestone,
+    Event,
+    MinecraftRelease,
+}
+
+impl MarkerKind {
+    fn class(self) -> &'static str {
+        match self {
+            MarkerKind::Milestone => "milestone",
+            MarkerKind::Event => "event",
+            MarkerKind::MinecraftRelease => "release",
+        }
+    }
+
+    fn color(self, palette: &ChartPalette) -> RGBColor {
+        match self {
+            MarkerKind::Milestone => palette.milestone,
+            MarkerKind::Event => palette.event,
+            MarkerKind::MinecraftRelease => palette.release,
+        }
+    }
+}
+
+/// A shaded span of the plot between times `start` and `end`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartRegion {
+    pub start: i64,
+    pub end: i64,
+    pub color: RGBColor,
+}
+
+/// A dashed line bridging the points either side of a gap in a series, with a
+/// `description` shown when it is hovered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartConnector {
+    pub from: (i64, f64),
+    pub to: (i64, f64),
+    pub color: RGBColor,
+    pub description: String,
+}
+
+/// Everything drawn over or behind the plotted series.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overlays {
+    pub markers: Vec<ChartMarker>,
+    pub regions: Vec<ChartRegion>,
+    pub connectors: Vec<ChartConnector>,
+}
+
+/// A span of a chart's time axis, in unix milliseconds, as zoomed to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl TimeWindow {
+    /// The narrowest window zooming goes down to, an hour.
+    pub const MIN_SPAN: i64 = 3_600_000;
+
+    pub fn span(&self) -> i64 {
+        self.end - self.start
+    }
+
+    pub fn contains(&self, t: i64) -> bool {
+        (self.start..=self.end).contains(&t)
+    }
+
+    /// The window moved and narrowed as little as possible to fit in
+    /// `bounds`, and widened to at least [`Self::MIN_SPAN`] where `bounds`
+    /// allow.
+    pub fn within(self, bounds: TimeWindow) -> TimeWindow {
+        let span = self.span().max(Self::MIN_SPAN).min(bounds.span()).max(0);
+        let start = self.start.clamp(bounds.start, bounds.end - span);
+        TimeWindow {
+            start,
+            end: start + span,
+        }
+    }
+
+    /// The window scaled by `factor` (below 1 zooms in) so `around` stays at
+    /// the same place, fitted to `bounds`.
+    pub fn zoom(self, factor: f64, around: i64, bounds: TimeWindow) -> Self {
+        let scale = |t: i64| around + ((t - around) as f64 * factor) as i64;
+        TimeWindow {
+            start: scale(self.start),
+            end: scale(self.end),
+        }
+        .within(bounds)
+    }
+
+    /// The window moved by `by` milliseconds, fitted to `bounds`.
+    pub fn pan(self, by: i64, bounds: TimeWindow) -> TimeWindow {
+        TimeWindow {
+            start: self.start + by,
+            end: self.end + by,
+        }
+        .within(bounds)
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = |t: i64| {
+            DateTime::from_timestamp_millis(t)
+                .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .unwrap_or_default()
+        };
+        write!(f, "{}..{}", time(self.start), time(self.end))
+    }
+}
+
+impl FromStr for TimeWindow {
+    type Err = String;
+
+    /// Parses `START..END`, both RFC 3339 times.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let time = |t: &str| {
+            DateTime::parse_from_rfc3339(t)
+                .map(|t| t.timestamp_millis())
+                .map_err(|err| format!("invalid time '{t}': {err}"))
+        };
+        let (start, end) = s
+            .split_once("..")
+            .ok_or_else(|| format!("expected START..END, got '{s}'"))?;
+        let window = TimeWindow {
+            start: time(start)?,
+            end: time(end)?,
+        };
+        match window.start < window.end {
+            true => Ok(window),
+            false => Err(format!("window '{s}' ends before it starts")),
+        }
+    }
+}
+
+/// Where a rendered chart's time axis lies: the horizontal pixel span of its
+/// plot area and the times at either end. Written onto the chart's `<svg>`
+/// as data attributes, so the page can map pointer positions to times.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeAxis {
+    pub left: f64,
+    pub right: f64,
+    pub window: TimeWindow,
+}
+
+impl TimeAxis {
+    /// Reads the axis back from a chart's attributes, as looked up by `attr`.
+    pub fn from_attributes(
+        attr: impl Fn(&str) -> Option<String>,
+    ) -> Option<Self> {
+        let number = |name: &str| attr(name)?.parse::<f64>().ok();
+        Some(TimeAxis {
+            left: number("data-plot-left")?,
+            right: number("data-plot-right")?,
+            window: TimeWindow {
+                start: number("data-start")? as i64,
+                end: number("data-end")? as i64,
+            },
+        })
+    }
+
+    /// The time at horizontal pixel `x` of the chart, clamped to the axis.
+    pub fn time_at(&self, x: f64) -> i64 {
+        let fraction =
+            ((x - self.left) / (self.right - self.left)).clamp(0.0, 1.0);
+        self.window.start + (fraction * self.window.span() as f64) as i64
+    }
+
+    /// The horizontal pixel of time `t`.
+    pub fn x_at(&self, t: i64) -> f64 {
+        let fraction =
+            (t - self.window.start) as f64 / self.window.span().max(1) as f64;
+        self.left + fraction * (self.right - self.left)
+    }
+
+    fn attributes(&self) -> String {
+        format!(
+            r#"data-plot-left="{}" data-plot-right="{}" data-start="{}" data-end="{}""#,
+            self.left, self.right, self.window.start, self.window.end
+        )
+    }
+
+    /// `svg` with the axis written onto its root element.
+    fn tag(&self, svg: &str) -> String {
+        svg.replacen("<svg ", &format!("<svg {} ", self.attributes()), 1)
+    }
+}
+
+/// The time span of a mod's overall stats, which every chart of it covers
+/// when not zoomed.
+pub fn time_bounds(mod_stats: &ModStatsResponse) -> Option<TimeWindow> {
+    let timestamps = parse_to_timestamps(&mod_stats.overall_stats);
+    Some(TimeWindow {
+        start: *timestamps.iter().min()?,
+        end: *timestamps.iter().max()?,
+    })
+}
+
+/// The window a chart for `options` shows: its range fitted to the mod's
+/// history, or the whole history when not zoomed.
+pub fn chart_window(
+    mod_stats: &ModStatsResponse,
+    options: &ChartOptions,
+) -> Option<TimeWindow> {
+    let bounds = time_bounds(mod_stats)?;
+    Some(options.range.map_or(bounds, |range| range.within(bounds)))
+}
+
+/// Settings for [`mod_stats_svg`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartOptions {
+    /// Intervals over this many times a series' typical sampling interval
+    /// are drawn as gaps rather than lines.
+    pub gap_factor: f64,
+    /// Plot each series resampled to this bucket size rather than at its raw
+    /// data points.
+    pub bucket: Option<Bucket>,
+    /// How resampled buckets are filled in between data points.
+    pub fill: Fill,
+    /// Events marked on the chart, where they fall within its time range.
+    pub annotations: Vec<Annotation>,
+    /// Whether series are plotted as totals, per interval or indexed.
+    pub mode: DisplayMode,
+    /// Plot only this part of the time axis, with the value axis fitted to
+    /// the points within it, rather than the whole history.
+    pub range: Option<TimeWindow>,
+    /// Colours the chart is drawn in.
+    pub palette: ChartPalette,
+    /// How dates and numbers on the chart are written.
+    pub locale: Locale,
+    /// The time zone dates on the chart are written in, and resampled days
+    /// start at midnight in.
+    pub time_zone: Tz,
+}
+
+impl Default for ChartOptions {
+    fn default() -> Self {
+        Self {
+            gap_factor: DEFAULT_GAP_FACTOR,
+            bucket: None,
+            fill: Fill::default(),
+            annotations: Vec::new(),
+            mode: DisplayMode::default(),
+            range: None,
+            palette: ChartPalette::default(),
+            locale: Locale::default(),
+            time_zone: Tz::UTC,
+        }
+    }
+}
+
+/// Unix time in milliseconds of an RFC 3339 timestamp, or `None` if it
+/// doesn't parse.
+pub fn parse_to_timestamp(s: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|t| t.timestamp_millis())
+}
+
+/// The times of `s`'s points, skipping any with unparseable timestamps.
+pub fn parse_to_timestamps(s: &[TimestampedModStats]) -> Vec<i64> {
+    s.iter()
+        .filter_map(|s| parse_to_timestamp(&s.timestamp))
+        .collect()
+}
+
+/// One source's series as the downloads chart plots it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlottedSeries {
+    pub source: Source,
+    /// Runs of continuous `(unix time in milliseconds, value)` points, as
+    /// values in the chart's display mode and within its time window.
+    pub segments: Vec<Vec<(i64, f64)>>,
+    /// Gaps in the series' data, bridged by connectors.
+    pub gaps: Vec<Gap>,
+}
+
+/// Every source's series as [`mod_stats_svg`] plots them for `options`, in
+/// the order their points are drawn.
+pub fn plotted_series(
+    mod_stats: &ModStatsResponse,
+    options: &ChartOptions,
+) -> Vec<PlottedSeries> {
+    let Some(window) = chart_window(mod_stats, options) else {
+        return Vec::new();
+    };
+    Source::ALL
+        .into_iter()
+        .map(|source| {
+            let mut points = sorted_points(source.series(mod_stats));
+            let gaps =
+                gaps::detect_in_points(source, &points, options.gap_factor);
+            if let Some(bucket) = options.bucket {
+                points = resample(
+                    &points,
+                    bucket,
+                    options.fill,
+                    &gaps,
+                    options.time_zone,
+                );
+            }
+            let segments =
+                transform(&split_at_gaps(&points, &gaps), options.mode)
+                    .into_iter()
+                    .map(|segment| {
+                        segment
+                            .into_iter()
+                            .filter(|&(t, _)| window.contains(t))
+                            .collect::<Vec<_>>()
+                    })
+                    .filter(|segment| !segment.is_empty())
+                    .collect();
+            PlottedSeries {
+                source,
+                segments,
+                gaps,
+            }
+        })
+        .collect()
+}
+
+/// A sentence on how a mod's overall downloads changed over a chart's time
+/// window, written for `locale` with dates in `time_zone`, e.g. "Downloads
+/// grew from 1,200 to 5,400 between 01/01/2024 and 03/01/2024."
+pub fn downloads_summary(
+    mod_stats: &ModStatsResponse,
+    range: Option<TimeWindow>,
+    locale: Locale,
+    time_zone: Tz,
+) -> String {
+    let Some(bounds) = time_bounds(mod_stats) else {
+        return "No download data.".to_string();
+    };
+    let window = range.map_or(bounds, |range| range.within(bounds));
+    let points: Vec<(i64, i64)> =
+        sorted_points(Source::Overall.series(mod_stats))
+            .into_iter()
+            .filter(|&(t, _)| window.contains(t))
+            .collect();
+
+    match (points.first(), points.last()) {
+        (Some(&(start, first)), Some(&(end, last))) if start < end => {
+            let (from, to) = (
+                locale.date_at(start, time_zone),
+                locale.date_at(end, time_zone),
+            );
+            let (first_count, last_count) =
+                (locale.number(first), locale.number(last));
+            match last.cmp(&first) {
+                std::cmp::Ordering::Greater => format!(
+                    "Downloads grew from {first_count} to {last_count} \
+                     between {from} and {to}."
+                ),
+                std::cmp::Ordering::Less => format!(
+                    "Downloads fell from {first_count} to {last_count} \
+                     between {from} and {to}."
+                ),
+                std::cmp::Ordering::Equal => format!(
+                    "Downloads stayed at {first_count} between {from} and \
+                     {to}."
+                ),
+            }
+        }
+        (Some(&(t, downloads)), _) => format!(
+            "{} downloads on {}.",
+            locale.number(downloads),
+            locale.date_at(t, time_zone)
+        ),
+        _ => "No download data in this period.".to_string(),
+    }
+}
+
+/// Renders downloads over time for every source as an SVG string. Each point's
+/// circle is tagged with `data-x` (timestamp), `data-label` (its time) and
+/// `data-y` (its value, as formatted for the display mode) attributes for the
+/// tooltips in `circles.js`, written for the options' locale and time zone.
+/// Lines are broken across gaps in the data, with a dashed line bridging each
+/// gap. With a bucket size set, the points plotted are the series resampled to
+/// it. With a range set, only the points within it are plotted, and the root
+/// `<svg>` carries its [`TimeAxis`] either way. The chart is an image titled
+/// with its caption and described by [`downloads_summary`].
+pub fn mod_stats_svg(
+    mod_stats: &ModStatsResponse,
+    options: &ChartOptions,
+) -> DrawResult<String> {
+    let Some(window) = chart_window(mod_stats, options) else {
+        return Err("no stats to plot".into());
+    };
+    let padding_frac = 0.3;
+
+    let mut series = Vec::new();
+    // in the order their circles are drawn
+    let mut plotted_points = Vec::new();
+    let mut connectors = Vec::new();
+    for plotted in plotted_series(mod_stats, options) {
+        let color = options.palette.series_color(plotted.source);
+        let flattened: Vec<(i64, f64)> =
+            plotted.segments.iter().flatten().copied().collect();
+        connectors.extend(plotted.gaps.iter().filter_map(|gap| {
+            gap_connector(
+                gap,
+                color,
+                &flattened,
+                options.locale,
+                options.time_zone,
+            )
+        }));
+        for segment in plotted.segments {
+            plotted_points.extend_from_slice(&segment);
+            series.push(LineSeries::new(segment, color));
+        }
+    }
+
+    // cumulative and indexed values only grow, so their axis starts at zero;
+    // incremental ones can go negative when a platform's count is corrected
+    let (min_value, max_value) = plotted_points
+        .iter()
+        .fold((0f64, 1f64), |(min, max), &(_, y)| (min.min(y), max.max(y)));
+    let y_range =
+        min_value * (1.0 + padding_frac)..max_value * (1.0 + padding_frac);
+
+    let svg_string = draw_series(
+        window.start..window.end,
+        y_range,
+        options,
+        series,
+        &Overlays {
+            markers: milestone_markers(
+                &mod_stats.overall_stats,
+                options.locale,
+                options.time_zone,
+            )
+            .into_iter()
+            .chain(options.annotations.iter().map(|annotation| {
+                annotation_marker(annotation, options.locale, options.time_zone)
+            }))
+            .collect(),
+            regions: anomaly_regions(
+                &anomalies::detect(mod_stats, options.gap_factor),
+                &options.palette,
+            ),
+            connectors,
+        },
+    )?;
+
+    let svg = annotate_points(
+        &svg_string,
+        &plotted_points,
+        options.mode,
+        options.locale,
+        options.time_zone,
+    )?;
+    Ok(describe_svg(
+        &svg,
+        options.mode.caption(),
+        &downloads_summary(
+            mod_stats,
+            options.range,
+            options.locale,
+            options.time_zone,
+        ),
+    ))
+}
+
+/// A connector between the plotted points either side of `gap`, if it has
+/// points on both sides.
+fn gap_connector(
+    gap: &Gap,
+    color: RGBColor,
+    points: &[(i64, f64)],
+    locale: Locale,
+    time_zone: Tz,
+) -> Option<ChartConnector> {
+    let from = points.iter().rev().find(|(t, _)| *t <= gap.start)?;
+    let to = points.iter().find(|(t, _)| *t >= gap.end)?;
+    Some(ChartConnector {
+        from: *from,
+        to: *to,
+        color,
+        description: format!(
+            "{}: no data for {} days ({} to {})",
+            gap.source.label(),
+            locale.decimal(gap.days(), 1),
+            locale.date_at(gap.start, time_zone),
+            locale.date_at(gap.end, time_zone)
+        ),
+    })
+}
+
+/// Markers where the series' cumulative downloads crossed round numbers,
+/// spaced to suit its scale, labelled for `locale` with dates in `time_zone`.
+pub fn milestone_markers(
+    series: &[TimestampedModStats],
+    locale: Locale,
+    time_zone: Tz,
+) -> Vec<ChartMarker> {
+    let Some((first, max)) = download_range(series) else {
+        return Vec::new();
+    };
+    let reached =
+        milestones(series, scaled_thresholds(first, max, MILESTONE_MARKERS));
+
+    let mut previous: Option<(i64, i64)> = None;
+    reached
+        .into_iter()
+        .map(|milestone| {
+            let label = locale.compact(milestone.threshold as f64);
+            let date = locale.date_at(milestone.reached_at, time_zone);
+            let since_previous = match previous {
+                Some((threshold, at)) => format!(
+                    "{} days after {}",
+                    (milestone.reached_at - at) / 86_400_000,
+                    locale.compact(threshold as f64)
+                ),
+                None => "first milestone in the data".to_string(),
+            };
+            previous = Some((milestone.threshold, milestone.reached_at));
+
+            ChartMarker {
+                x: milestone.reached_at,
+                description: format!(
+                    "{label} downloads on {date}\n{since_previous}"
+                ),
+                label,
+                kind: MarkerKind::Milestone,
+            }
+        })
+        .collect()
+}
+
+/// A marker at the start of an annotation's day in `time_zone`, its date
+/// written for `locale`.
+pub fn annotation_marker(
+    annotation: &Annotation,
+    locale: Locale,
+    time_zone: Tz,
+) -> ChartMarker {
+    let date = locale.date(annotation.date);
+    let description = match &annotation.description {
+        Some(description) => format!("{description}\n{date}"),
+        None => format!("{} on {date}", annotation.label),
+    };
+    ChartMarker {
+        x: day_start(annotation.date, time_zone).timestamp_millis(),
+        label: annotation.label.clone(),
+        description,
+        kind: match annotation.kind {
+            AnnotationKind::Event => MarkerKind::Event,
+            AnnotationKind::MinecraftRelease => MarkerKind::MinecraftRelease,
+        },
+    }
+}
+
+/// Shading for detected anomalies, coloured by kind.
+pub fn anomaly_regions(
+    anomalies: &[Anomaly],
+    palette: &ChartPalette,
+) -> Vec<ChartRegion> {
+    anomalies
+        .iter()
+        .map(|anomaly| ChartRegion {
+            start: anomaly.start,
+            end: anomaly.end,
+            color: match anomaly.kind {
+                AnomalyKind::Spike => palette.spike,
+                AnomalyKind::Flat => palette.flat,
+                AnomalyKind::Drop => palette.drop,
+            },
+        })
+        .collect()
+}
+
+/// Tags the `n`th circle in `svg` with the `(timestamp, value)` of
+/// `points[n]`, the value formatted for `mode`, and the time in `time_zone`
+/// as written for `locale`.
+fn annotate_points(
+    svg: &str,
+    points: &[(i64, f64)],
+    mode: DisplayMode,
+    locale: Locale,
+    time_zone: Tz,
+) -> DrawResult<String> {
+    let mut svg_reader = ::quick_xml::reader::Reader::from_str(svg);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+
+    let mut idx = 0;
+    loop {
+        match svg_reader.read_event()? {
+            // exits the loop when reaching end of file
+            Event::Eof => break,
+
+            Event::Empty(e) if e.name().as_ref() == b"circle" => {
+                tracing::trace!(idx, "annotating chart point");
+                let (timestamp, value) = points[idx];
+                idx += 1;
+                let label = locale.date_time_at(timestamp, time_zone);
+                let timestamp = DateTime::from_timestamp_millis(timestamp)
+                    .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+                    .unwrap_or_default();
+                let mut elem = e.into_owned();
+                elem.push_attribute((
+                    "data-y",
+                    &*mode.point_label(value, locale),
+                ));
+                elem.push_attribute(("data-x", &*timestamp));
+                elem.push_attribute(("data-label", &*label));
+                writer.write_event(Event::Empty(elem))?;
+            }
+            e => writer.write_event(e)?,
+        }
+    }
+    let svg_blob = writer.into_inner().into_inner();
+    Ok(String::from_utf8(svg_blob)?)
+}
+
+/// Gives the `n`th `element` in `svg` a `<title>` of `titles[n]`, for as
+/// many titles as there are, so it is shown when the element is hovered.
+fn add_titles(
+    svg: &str,
+    element: &str,
+    titles: &[String],
+) -> DrawResult<String> {
+    let mut svg_reader = ::quick_xml::reader::Reader::from_str(svg);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+
+    let mut titles = titles.iter();
+    loop {
+        match svg_reader.read_event()? {
+            // exits the loop when reaching end of file
+            Event::Eof => break,
+
+            Event::Empty(e) if e.name().as_ref() == element.as_bytes() => {
+                let Some(title) = titles.next() else {
+                    writer.write_event(Event::Empty(e))?;
+                    continue;
+                };
+                writer.write_event(Event::Start(e.into_owned()))?;
+                writer.write_event(Event::Start(BytesStart::new("title")))?;
+                writer.write_event(Event::Text(BytesText::new(title)))?;
+                writer.write_event(Event::End(BytesEnd::new("title")))?;
+                writer.write_event(Event::End(BytesEnd::new(element)))?;
+            }
+            e => writer.write_event(e)?,
+        }
+    }
+    let svg_blob = writer.into_inner().into_inner();
+    Ok(String::from_utf8(svg_blob)?)
+}
+
+/// Marks `svg` as an image named `title`, with `description` as its
+/// accessible description, for screen readers.
+fn describe_svg(svg: &str, title: &str, description: &str) -> String {
+    let Some(root_end) = svg
+        .find("<svg ")
+        .and_then(|start| svg[start..].find('>').map(|end| start + end + 1))
+    else {
+        return svg.to_string();
+    };
+    format!(
+        "{}<title>{}</title><desc>{}</desc>{}",
+        svg[..root_end].replacen("<svg ", r#"<svg role="img" "#, 1),
+        escape(title),
+        escape(description),
+        &svg[root_end..]
+    )
+}
+
+/// Type alias for the result of a drawing function.
+pub type DrawResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+pub fn draw_series(
+    x_range: Range<i64>,
+    y_range: Range<f64>,
+    options: &ChartOptions,
+    series: Vec<LineSeries<SVGBackend, (i64, f64)>>,
+    overlays: &Overlays,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (mode, palette, locale, time_zone) = (
+        options.mode,
+        &options.palette,
+        options.locale,
+        options.time_zone,
+    );
+    let mut svg_content: String = "".to_string();
+    let (x_bounds, y_bounds) = (x_range.clone(), y_range.clone());
+    let overlay_svg: String;
+    let axis: TimeAxis;
+    {
+        let backend = SVGBackend::with_string(&mut svg_content, (800, 600));
+        let root = backend.into_drawing_area();
+        if let Some(background) = palette.background {
+            root.fill(&background)?;
+        }
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(64u32)
+            .caption(mode.caption(), palette.text_style(20.0))
+            .x_label_area_size(30u32)
+            .y_label_area_size(30u32)
+            .build_cartesian_2d(x_range, y_range)?;
+
+        chart
+            .configure_mesh()
+            .axis_style(palette.grid)
+            .bold_line_style(palette.grid.mix(0.2))
+            .light_line_style(palette.grid.mix(0.1))
+            .label_style(palette.text_style(12.0))
+            .x_labels(5)
+            .y_labels(8)
+            .x_label_formatter(&|v| locale.date_at(*v, time_zone))
+            .y_label_formatter(&|v| mode.axis_label(*v, locale))
+            .draw()?;
+
+        // behind the series, so the lines stay readable
+        chart.draw_series(overlays.regions.iter().filter_map(|region| {
+            let start = region.start.max(x_bounds.start);
+            let end = region.end.min(x_bounds.end);
+            (start < end).then(|| {
+                Rectangle::new(
+                    [(start, y_bounds.start), (end, y_bounds.end)],
+                    region.color.mix(0.15).filled(),
+                )
+            })
+        }))?;
+
+        for s in series {
+            chart.draw_series(s.point_size(2))?;
+        }
+
+        // plotters can't attach hover text to elements (or dash lines), so
+        // markers and connectors are written as SVG by hand at the positions
+        // plotters would use
+        let (plot_x, plot_y) = chart.plotting_area().get_pixel_range();
+        axis = TimeAxis {
+            left: plot_x.start as f64,
+            right: plot_x.end as f64,
+            window: TimeWindow {
+                start: x_bounds.start,
+                end: x_bounds.end,
+            },
+        };
+        let mut visible_markers: Vec<&ChartMarker> = overlays
+            .markers
+            .iter()
+            .filter(|marker| x_bounds.contains(&marker.x))
+            .collect();
+        visible_markers.sort_by_key(|marker| marker.x);
+        let markers =
+            visible_markers
+                .into_iter()
+                .enumerate()
+                .map(|(idx, marker)| {
+                    let (x, _) =
+                        chart.backend_coord(&(marker.x, y_bounds.start));
+                    // alternate label rows, so neighbouring labels don't
+                    // overlap
+                    let label_y = plot_y.start + 12 + (idx % 2) as i32 * 14;
+                    marker_element(marker, palette, x, plot_y.clone(), label_y)
+                });
+        let connectors = overlays.connectors.iter().map(|connector| {
+            connector_element(
+                connector,
+                chart.backend_coord(&connector.from),
+                chart.backend_coord(&connector.to),
+            )
+        });
+        overlay_svg = connectors.chain(markers).collect();
+
+        root.present()?;
+    }
+
+    if let Some(end) = svg_content.rfind("</svg>") {
+        svg_content.insert_str(end, &overlay_svg);
+    }
+    Ok(axis.tag(&svg_content))
+}
+
+/// A marker as an SVG group classed by its kind: a dashed rule, a wider
+/// invisible rule that is easier to hover, and a label, with the description
+/// as a `<title>`.
+fn marker_element(
+    marker: &ChartMarker,
+    palette: &ChartPalette,
+    x: i32,
+    y: Range<i32>,
+    label_y: i32,
+) -> String {
+    let color = hex(marker.kind.color(palette));
+    let class = marker.kind.class();
+    let (y1, y2) = (y.start, y.end);
+    let rule = |style: &str| {
+        format!(
+            r#"<line x1="{x}" y1="{y1}" x2="{x}" y2="{y2}" stroke="{color}" {style}/>"#
+        )
+    };
+    let hover_target = rule(r#"stroke-opacity="0" stroke-width="9""#);
+    let dashed = rule(r#"stroke-width="1" stroke-dasharray="4 3""#);
+    let label_x = x + 3;
+
+    format!(
+        r#"<g class="{class}"><title>{}</title>{hover_target}{dashed}<text x="{label_x}" y="{label_y}" font-family="sans-serif" font-size="12" fill="{color}">{}</text></g>"#,
+        escape(&marker.description),
+        escape(&marker.label),
+    )
+}
+
+/// A connector as a dashed polyline, with the description as a `<title>`.
+fn connector_element(
+    connector: &ChartConnector,
+    (x1, y1): (i32, i32),
+    (x2, y2): (i32, i32),
+) -> String {
+    format!(
+        r#"<g class="gap"><title>{}</title><polyline points="{x1},{y1} {x2},{y2}" fill="none" stroke="{}" stroke-width="1" stroke-dasharray="6 4"/></g>"#,
+        escape(&connector.description),
+        hex(connector.color),
+    )
+}
+
+fn hex(RGBColor(r, g, b): RGBColor) -> String {
+    format!("#{r:02X}{g:02X}{b:02X}")
+}
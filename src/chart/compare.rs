@@ -0,0 +1,159 @@
+//! Rendering of a [`Comparison`] as two periods' daily downloads overlaid on
+//! a shared axis of days since each period's start.
+
+use plotters::prelude::*;
+
+use super::palette::ChartPalette;
+use super::{add_titles, describe_svg, DrawResult};
+use crate::analysis::compare::{Comparison, PeriodDownloads};
+use crate::format::Locale;
+
+/// Renders `comparison` as an SVG string, the current period in the palette's
+/// first series colour over the previous one. Each point's circle has a
+/// `<title>` with its date and downloads, shown on hover, and the chart is
+/// described by [`comparison_summary`], all written for `locale`.
+pub fn comparison_svg(
+    comparison: &Comparison,
+    palette: &ChartPalette,
+    locale: Locale,
+) -> DrawResult<String> {
+    let periods = [
+        (&comparison.current, palette.series[0]),
+        (&comparison.previous, palette.previous_period),
+    ];
+    let days = periods
+        .iter()
+        .map(|(period, _)| period.daily.len() as i64)
+        .max()
+        .unwrap_or(0);
+    if days == 0 {
+        return Err("no days to compare".into());
+    }
+    let known = || {
+        periods
+            .iter()
+            .flat_map(|(period, _)| period.daily.iter().flatten().copied())
+    };
+    let lowest = known().min().unwrap_or(0).min(0);
+    let highest = known().max().unwrap_or(0).max(1);
+    let padding = (highest - lowest) / 5;
+
+    let mut svg_content = String::new();
+    // in the order their circles are drawn
+    let mut titles = Vec::new();
+    {
+        let backend = SVGBackend::with_string(&mut svg_content, (800, 400));
+        let root = backend.into_drawing_area();
+        if let Some(background) = palette.background {
+            root.fill(&background)?;
+        }
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(32u32)
+            .caption("Daily Downloads by Period", palette.text_style(20.0))
+            .x_label_area_size(40u32)
+            .y_label_area_size(50u32)
+            .build_cartesian_2d(
+                0i64..(days - 1).max(1),
+                lowest..highest + padding,
+            )?;
+
+        chart
+            .configure_mesh()
+            .axis_style(palette.grid)
+            .bold_line_style(palette.grid.mix(0.2))
+            .light_line_style(palette.grid.mix(0.1))
+            .label_style(palette.text_style(12.0))
+            .x_desc("Day of period")
+            .y_desc("Downloads")
+            .x_label_formatter(&|day| locale.number(day + 1))
+            .y_label_formatter(&|downloads| locale.compact(*downloads as f64))
+            .draw()?;
+
+        for (period, color) in periods {
+            let mut labelled = false;
+            for run in known_runs(period) {
+                titles.extend(run.iter().map(|&(day, downloads)| {
+                    let date =
+                        period.period.start + chrono::Days::new(day as u64);
+                    format!(
+                        "Day {}, {}: {} downloads",
+                        day + 1,
+                        locale.date(date),
+                        locale.number(downloads)
+                    )
+                }));
+                let drawn = chart
+                    .draw_series(LineSeries::new(run, color).point_size(2))?;
+                if !labelled {
+                    drawn.label(period.period.describe(locale)).legend(
+                        move |(x, y)| {
+                            PathElement::new([(x, y), (x + 20, y)], color)
+                        },
+                    );
+                    labelled = true;
+                }
+            }
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(palette.background.unwrap_or(WHITE).mix(0.8))
+            .border_style(palette.grid)
+            .label_font(palette.text_style(12.0))
+            .draw()?;
+
+        root.present()?;
+    }
+
+    let svg = add_titles(&svg_content, "circle", &titles)?;
+    Ok(describe_svg(
+        &svg,
+        "Daily downloads by period",
+        &comparison_summary(comparison, locale),
+    ))
+}
+
+/// Sentences on both periods' downloads and the change between them, written
+/// for `locale`.
+pub fn comparison_summary(comparison: &Comparison, locale: Locale) -> String {
+    let period = |name: &str, downloads: &PeriodDownloads| {
+        let mean = downloads
+            .mean
+            .map(|mean| format!(", {} per day", locale.decimal(mean, 0)))
+            .unwrap_or_default();
+        format!(
+            "{name} period, {}: {} downloads{mean}.",
+            downloads.period.describe(locale),
+            locale.number(downloads.total)
+        )
+    };
+    let change = match comparison.change {
+        Some(change) => format!(
+            " Mean daily downloads changed by {}%.",
+            locale.signed(change, 1)
+        ),
+        None => String::new(),
+    };
+    format!(
+        "{} {}{change}",
+        period("Current", &comparison.current),
+        period("Previous", &comparison.previous)
+    )
+}
+
+/// The runs of consecutive days with data in `period`, as `(day index,
+/// downloads)`.
+fn known_runs(period: &PeriodDownloads) -> Vec<Vec<(i64, i64)>> {
+    let mut runs = vec![Vec::new()];
+    for (day, downloads) in period.daily.iter().enumerate() {
+        match downloads {
+            Some(downloads) => {
+                runs.last_mut().unwrap().push((day as i64, *downloads))
+            }
+            None => runs.push(Vec::new()),
+        }
+    }
+    runs.retain(|run| !run.is_empty());
+    runs
+}
@@ -0,0 +1,236 @@
+//! A calendar heatmap of downloads per day over the last year, one cell per
+//! day in the style of GitHub's contribution graph, so weekly and seasonal
+//! patterns stand out.
+
+use chrono::{DateTime, Datelike, Days, NaiveDate};
+use chrono_tz::Tz;
+use fibermc_sdk::models::ModStatsResponse;
+use plotters::prelude::*;
+
+use super::palette::ChartPalette;
+use super::{add_titles, describe_svg, DrawResult};
+use crate::analysis::resample::daily_downloads;
+use crate::analysis::{sorted_points, Source};
+use crate::format::Locale;
+
+/// Columns drawn, enough for a full year whatever weekday it ends on.
+const WEEKS: u64 = 53;
+const CELL_SIZE: i32 = 11;
+/// Distance between the starts of neighbouring cells.
+const CELL_STEP: i32 = 13;
+const LEFT_MARGIN: i32 = 32;
+const TOP_MARGIN: i32 = 20;
+
+/// Downloads on one day, if known.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeatmapDay {
+    pub date: NaiveDate,
+    pub downloads: Option<i64>,
+}
+
+/// Downloads per day in `time_zone` of one series of `stats`, from the Monday
+/// 52 weeks before its latest data point up to that point, oldest first. Days
+/// the series doesn't fully cover, or that fall in a gap, have no value.
+pub fn heatmap_days(
+    stats: &ModStatsResponse,
+    source: Source,
+    time_zone: Tz,
+) -> Vec<HeatmapDay> {
+    let points = sorted_points(source.series(stats));
+    let Some(last) = points
+        .last()
+        .and_then(|&(t, _)| DateTime::from_timestamp_millis(t))
+        .map(|t| t.with_timezone(&time_zone).date_naive())
+    else {
+        return Vec::new();
+    };
+
+    let downloads = daily_downloads(stats, source, time_zone);
+
+    let first = last
+        - Days::new(
+            7 * (WEEKS - 1) + last.weekday().num_days_from_monday() as u64,
+        );
+    first
+        .iter_days()
+        .take_while(|date| *date <= last)
+        .map(|date| HeatmapDay {
+            date,
+            downloads: downloads.get(&date).copied(),
+        })
+        .collect()
+}
+
+/// A sentence summing up `days` of one source, written for `locale`, e.g.
+/// "Overall: 5,400 downloads over 360 days with data from 01/01/2024 to
+/// 12/31/2024. The busiest day was 06/13/2024, with 120."
+pub fn heatmap_summary(
+    days: &[HeatmapDay],
+    source: Source,
+    locale: Locale,
+) -> String {
+    let known: Vec<(NaiveDate, i64)> = days
+        .iter()
+        .filter_map(|day| Some((day.date, day.downloads?)))
+        .collect();
+    let (Some((first, _)), Some((last, _))) = (known.first(), known.last())
+    else {
+        return format!("{}: no daily downloads data.", source.label());
+    };
+    let total: i64 = known.iter().map(|(_, downloads)| downloads).sum();
+    let mut summary = format!(
+        "{}: {} downloads over {} days with data from {} to {}.",
+        source.label(),
+        locale.number(total),
+        locale.number(known.len() as i64),
+        locale.date(*first),
+        locale.date(*last)
+    );
+    if let Some((date, downloads)) = known
+        .iter()
+        .filter(|(_, downloads)| *downloads > 0)
+        .max_by_key(|(_, downloads)| *downloads)
+    {
+        summary.push_str(&format!(
+            " The busiest day was {}, with {}.",
+            locale.date(*date),
+            locale.number(*downloads)
+        ));
+    }
+    summary
+}
+
+/// Renders [`heatmap_days`] as an SVG string. Each day's cell has a `<title>`
+/// with its date and downloads, shown on hover, and the chart is described by
+/// [`heatmap_summary`]. Cells are coloured with the palette's heatmap levels,
+/// days run midnight to midnight in `time_zone`, and dates and counts are
+/// written for `locale`.
+pub fn calendar_heatmap_svg(
+    stats: &ModStatsResponse,
+    source: Source,
+    palette: &ChartPalette,
+    locale: Locale,
+    time_zone: Tz,
+) -> DrawResult<String> {
+    let days = heatmap_days(stats, source, time_zone);
+    if days.is_empty() {
+        return Err("no stats to plot".into());
+    }
+    let thresholds = level_thresholds(&days);
+
+    let width = LEFT_MARGIN + WEEKS as i32 * CELL_STEP + 8;
+    let height = TOP_MARGIN + 7 * CELL_STEP + 24;
+    let mut svg_content = String::new();
+    {
+        let backend = SVGBackend::with_string(
+            &mut svg_content,
+            (width as u32, height as u32),
+        );
+        let root = backend.into_drawing_area();
+        let font = palette.text_style(12.0);
+
+        // cells first, so they are the first rects in the document for
+        // `add_titles`
+        for (idx, day) in days.iter().enumerate() {
+            let (x, y) = cell_position(idx);
+            let color = match day.downloads {
+                Some(downloads) => {
+                    palette.heatmap[level(downloads, &thresholds)]
+                }
+                None => palette.no_data,
+            };
+            root.draw(&Rectangle::new(
+                [(x, y), (x + CELL_SIZE, y + CELL_SIZE)],
+                color.filled(),
+            ))?;
+        }
+
+        for (row, label) in [(0, "Mon"), (2, "Wed"), (4, "Fri")] {
+            let y = TOP_MARGIN + row * CELL_STEP;
+            root.draw(&Text::new(label, (0, y), font.clone()))?;
+        }
+        for (idx, day) in days.iter().enumerate() {
+            if day.date.day() == 1 {
+                let (x, _) = cell_position(idx);
+                let label = locale.month(day.date);
+                root.draw(&Text::new(
+                    label,
+                    (x, TOP_MARGIN - 14),
+                    font.clone(),
+                ))?;
+            }
+        }
+
+        // a key of the levels, from "Less" to "More"
+        let key_y = TOP_MARGIN + 7 * CELL_STEP + 8;
+        let key_x = width - 8 - palette.heatmap.len() as i32 * CELL_STEP - 58;
+        root.draw(&Text::new("Less", (key_x, key_y), font.clone()))?;
+        for (idx, color) in palette.heatmap.iter().enumerate() {
+            let x = key_x + 28 + idx as i32 * CELL_STEP;
+            root.draw(&Rectangle::new(
+                [(x, key_y), (x + CELL_SIZE, key_y + CELL_SIZE)],
+                color.filled(),
+            ))?;
+        }
+        let more_x = key_x + 32 + palette.heatmap.len() as i32 * CELL_STEP;
+        root.draw(&Text::new("More", (more_x, key_y), font.clone()))?;
+
+        root.present()?;
+    }
+
+    let titles: Vec<String> = days
+        .iter()
+        .map(|day| {
+            let date = locale.date(day.date);
+            match day.downloads {
+                Some(downloads) => {
+                    format!("{} downloads on {date}", locale.number(downloads))
+                }
+                None => format!("No data for {date}"),
+            }
+        })
+        .collect();
+    let svg = add_titles(&svg_content, "rect", &titles)?;
+    Ok(describe_svg(
+        &svg,
+        "Daily downloads calendar",
+        &heatmap_summary(&days, source, locale),
+    ))
+}
+
+/// Top left corner of the `idx`th day's cell, filling each week's column
+/// from Monday down.
+fn cell_position(idx: usize) -> (i32, i32) {
+    let (week, weekday) = (idx as i32 / 7, idx as i32 % 7);
+    (
+        LEFT_MARGIN + week * CELL_STEP,
+        TOP_MARGIN + weekday * CELL_STEP,
+    )
+}
+
+/// The lowest downloads for each of the three busiest levels: the quartiles
+/// of the days with any downloads.
+fn level_thresholds(days: &[HeatmapDay]) -> [i64; 3] {
+    let mut active: Vec<i64> = days
+        .iter()
+        .filter_map(|day| day.downloads)
+        .filter(|downloads| *downloads > 0)
+        .collect();
+    active.sort_unstable();
+    let quartile = |q: usize| {
+        active
+            .get(active.len() * q / 4)
+            .copied()
+            .unwrap_or(i64::MAX)
+    };
+    [quartile(1), quartile(2), quartile(3)]
+}
+
+/// The colour level of a day with `downloads`, an index into the palette's
+/// heatmap colours.
+fn level(downloads: i64, thresholds: &[i64; 3]) -> usize {
+    if downloads <= 0 {
+        return 0;
+    }
+    1 + thresholds.iter().filter(|t| downloads >= **t).count()
+}
@@ -0,0 +1,96 @@
+//! Rendering of the overview strip under the downloads chart: the whole
+//! history of overall downloads, with the window the chart is zoomed to
+//! highlighted.
+
+use chrono_tz::Tz;
+use fibermc_sdk::models::ModStatsResponse;
+use plotters::prelude::*;
+
+use super::palette::ChartPalette;
+use super::{time_bounds, DrawResult, TimeAxis, TimeWindow};
+use crate::analysis::{sorted_points, Source};
+use crate::format::Locale;
+
+/// Renders the strip as an SVG string, as wide as the downloads chart and
+/// with its plot area at the same horizontal pixels, so the two line up. The
+/// root `<svg>` carries the strip's [`TimeAxis`]. The history is drawn faded
+/// in the palette's text colour and the window in its first series colour,
+/// under month labels written for `locale`, in `time_zone`.
+pub fn navigator_svg(
+    mod_stats: &ModStatsResponse,
+    window: Option<TimeWindow>,
+    palette: &ChartPalette,
+    locale: Locale,
+    time_zone: Tz,
+) -> DrawResult<String> {
+    let Some(bounds) = time_bounds(mod_stats) else {
+        return Err("no stats to plot".into());
+    };
+    let points = sorted_points(Source::Overall.series(mod_stats));
+    let highest = points.iter().map(|&(_, d)| d).max().unwrap_or(0).max(1);
+
+    let mut svg_content = String::new();
+    let axis: TimeAxis;
+    {
+        let backend = SVGBackend::with_string(&mut svg_content, (800, 90));
+        let root = backend.into_drawing_area();
+
+        // the downloads chart's margin and label area, in the same places
+        let mut chart = ChartBuilder::on(&root)
+            .margin_left(64u32)
+            .margin_right(64u32)
+            .margin_top(4u32)
+            .margin_bottom(4u32)
+            .x_label_area_size(20u32)
+            .y_label_area_size(30u32)
+            .build_cartesian_2d(
+                bounds.start..bounds.end.max(bounds.start + 1),
+                0..highest,
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .disable_y_axis()
+            .axis_style(palette.grid)
+            // plotters' default size for an area this short
+            .label_style(palette.text_style(7.0))
+            .x_labels(5)
+            .x_label_formatter(&|v| {
+                chrono::DateTime::from_timestamp_millis(*v)
+                    .map(|d| {
+                        locale.month(d.with_timezone(&time_zone).date_naive())
+                    })
+                    .unwrap_or_default()
+            })
+            .draw()?;
+
+        chart.draw_series(AreaSeries::new(
+            points,
+            0,
+            palette.text.mix(0.3).filled(),
+        ))?;
+
+        if let Some(window) = window.map(|window| window.within(bounds)) {
+            chart.draw_series([Rectangle::new(
+                [(window.start, 0), (window.end, highest)],
+                palette.series[0].mix(0.2).filled(),
+            )])?;
+            chart.draw_series([Rectangle::new(
+                [(window.start, 0), (window.end, highest)],
+                palette.series[0].stroke_width(1),
+            )])?;
+        }
+
+        let (plot_x, _) = chart.plotting_area().get_pixel_range();
+        axis = TimeAxis {
+            left: plot_x.start as f64,
+            right: plot_x.end as f64,
+            window: bounds,
+        };
+
+        root.present()?;
+    }
+
+    Ok(axis.tag(&svg_content))
+}
@@ -0,0 +1,304 @@
+//! Colours of the charts. Text, grid and background follow the site's active
+//! theme, read from its CSS custom properties in the browser, and series use
+//! lighter shades on dark themes. A colour-blind-safe set, based on the
+//! Okabe-Ito palette, can be picked instead of the standard one.
+
+use std::fmt;
+use std::str::FromStr;
+
+use plotters::style::full_palette::{
+    BLUE_300, BLUE_600, BROWN_300, BROWN_600, GREEN_200, GREEN_300, GREEN_400,
+    GREEN_500, GREEN_600, GREEN_700, GREEN_800, GREEN_900, GREY_100, GREY_300,
+    GREY_400, GREY_600, GREY_700, GREY_800, ORANGE_300, ORANGE_600, PINK_300,
+    PINK_600, PURPLE_300, PURPLE_600, RED_300, RED_600,
+};
+use plotters::style::{IntoFont, RGBColor, TextStyle, BLACK, WHITE};
+
+use crate::analysis::Source;
+
+/// Which set of colours charts are drawn in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PaletteKind {
+    #[default]
+    Standard,
+    /// Colours told apart with any common colour vision deficiency.
+    ColorBlind,
+}
+
+impl PaletteKind {
+    pub const ALL: [PaletteKind; 2] =
+        [PaletteKind::Standard, PaletteKind::ColorBlind];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PaletteKind::Standard => "standard",
+            PaletteKind::ColorBlind => "color-blind",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PaletteKind::Standard => "Standard colours",
+            PaletteKind::ColorBlind => "Colour-blind safe",
+        }
+    }
+}
+
+impl fmt::Display for PaletteKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PaletteKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PaletteKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown palette '{s}', expected standard or color-blind"
+                )
+            })
+    }
+}
+
+/// Colours set by the site's active theme, where it sets them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ThemeColors {
+    pub text: Option<RGBColor>,
+    pub background: Option<RGBColor>,
+    /// Series colours, in [`Source::ALL`] order, for themes that pick their
+    /// own.
+    pub series: [Option<RGBColor>; 3],
+}
+
+impl ThemeColors {
+    pub const TEXT_TOKEN: &'static str = "--color-text";
+    pub const BACKGROUND_TOKEN: &'static str = "--color-base-1";
+    pub const SERIES_TOKENS: [&'static str; 3] =
+        ["--chart-series-1", "--chart-series-2", "--chart-series-3"];
+
+    /// Reads the theme's colours from its custom properties, as looked up by
+    /// `token`. Properties that are unset or aren't colours are skipped.
+    pub fn from_tokens(token: impl Fn(&str) -> Option<String>) -> Self {
+        let color = |name: &str| parse_css_color(&token(name)?);
+        ThemeColors {
+            text: color(Self::TEXT_TOKEN),
+            background: color(Self::BACKGROUND_TOKEN),
+            series: Self::SERIES_TOKENS.map(color),
+        }
+    }
+
+    /// Whether the theme is dark: a dark background, or failing that light
+    /// text.
+    pub fn is_dark(&self) -> bool {
+        match (self.background, self.text) {
+            (Some(background), _) => luminance(background) < 0.5,
+            (None, Some(text)) => luminance(text) > 0.5,
+            (None, None) => false,
+        }
+    }
+}
+
+/// Every colour a chart is drawn with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChartPalette {
+    /// Series colours, in [`Source::ALL`] order.
+    pub series: [RGBColor; 3],
+    /// The earlier period of a comparison, drawn behind the current one in
+    /// the first series colour.
+    pub previous_period: RGBColor,
+    pub text: RGBColor,
+    /// Axes, and grid lines drawn faded.
+    pub grid: RGBColor,
+    /// Filled behind the chart; transparent when unset.
+    pub background: Option<RGBColor>,
+    pub milestone: RGBColor,
+    pub event: RGBColor,
+    pub release: RGBColor,
+    pub spike: RGBColor,
+    pub flat: RGBColor,
+    pub drop: RGBColor,
+    /// Heatmap levels, from no downloads up to the busiest days.
+    pub heatmap: [RGBColor; 5],
+    /// Heatmap days with no data.
+    pub no_data: RGBColor,
+}
+
+impl ChartPalette {
+    /// The `kind` of palette suited to `theme`, with the theme's own text,
+    /// background and series colours where it sets them. Without a theme, as
+    /// when rendering on the server, the light one is assumed.
+    pub fn new(kind: PaletteKind, theme: Option<&ThemeColors>) -> Self {
+        let dark = theme.is_some_and(ThemeColors::is_dark);
+        let mut palette = match (kind, dark) {
+            (PaletteKind::Standard, false) => Self::STANDARD_LIGHT,
+            (PaletteKind::Standard, true) => Self::STANDARD_DARK,
+            (PaletteKind::ColorBlind, false) => Self::COLOR_BLIND_LIGHT,
+            (PaletteKind::ColorBlind, true) => Self::COLOR_BLIND_DARK,
+        };
+        if let Some(theme) = theme {
+            palette.text = theme.text.unwrap_or(palette.text);
+            palette.grid = palette.text;
+            palette.background = theme.background.or(palette.background);
+            // the colour-blind set is picked for telling series apart, so a
+            // theme's own series colours don't replace it
+            if kind == PaletteKind::Standard {
+                for (color, themed) in
+                    palette.series.iter_mut().zip(theme.series)
+                {
+                    *color = themed.unwrap_or(*color);
+                }
+            }
+        }
+        palette
+    }
+
+    pub fn series_color(&self, source: Source) -> RGBColor {
+        let idx = Source::ALL
+            .iter()
+            .position(|s| *s == source)
+            .unwrap_or_default();
+        self.series[idx]
+    }
+
+    /// Sans-serif text of `size` pixels in the text colour.
+    pub fn text_style(&self, size: f64) -> TextStyle<'static> {
+        ("sans-serif", size).into_font().color(&self.text)
+    }
+
+    const STANDARD_LIGHT: ChartPalette = ChartPalette {
+        series: [BLUE_600, GREEN_600, ORANGE_600],
+        previous_period: GREY_600,
+        text: BLACK,
+        grid: BLACK,
+        background: None,
+        milestone: BLUE_600,
+        event: PINK_600,
+        release: BROWN_600,
+        spike: RED_600,
+        flat: GREY_600,
+        drop: PURPLE_600,
+        heatmap: [GREY_300, GREEN_200, GREEN_400, GREEN_600, GREEN_800],
+        no_data: GREY_100,
+    };
+
+    const STANDARD_DARK: ChartPalette = ChartPalette {
+        series: [BLUE_300, GREEN_300, ORANGE_300],
+        previous_period: GREY_400,
+        text: WHITE,
+        grid: WHITE,
+        background: None,
+        milestone: BLUE_300,
+        event: PINK_300,
+        release: BROWN_300,
+        spike: RED_300,
+        flat: GREY_400,
+        drop: PURPLE_300,
+        heatmap: [GREY_700, GREEN_900, GREEN_700, GREEN_500, GREEN_300],
+        no_data: GREY_800,
+    };
+
+    const COLOR_BLIND_LIGHT: ChartPalette = ChartPalette {
+        series: [OKABE_ITO_BLUE, OKABE_ITO_GREEN, OKABE_ITO_ORANGE],
+        previous_period: GREY_600,
+        text: BLACK,
+        grid: BLACK,
+        background: None,
+        milestone: OKABE_ITO_SKY_BLUE,
+        event: OKABE_ITO_PURPLE,
+        release: OKABE_ITO_VERMILLION,
+        spike: OKABE_ITO_VERMILLION,
+        flat: GREY_600,
+        drop: OKABE_ITO_PURPLE,
+        heatmap: [GREY_300, VIRIDIS[3], VIRIDIS[2], VIRIDIS[1], VIRIDIS[0]],
+        no_data: GREY_100,
+    };
+
+    const COLOR_BLIND_DARK: ChartPalette = ChartPalette {
+        series: [OKABE_ITO_SKY_BLUE, OKABE_ITO_GREEN, OKABE_ITO_ORANGE],
+        previous_period: GREY_400,
+        text: WHITE,
+        grid: WHITE,
+        background: None,
+        milestone: OKABE_ITO_YELLOW,
+        event: OKABE_ITO_PURPLE,
+        release: OKABE_ITO_VERMILLION,
+        spike: OKABE_ITO_VERMILLION,
+        flat: GREY_400,
+        drop: OKABE_ITO_PURPLE,
+        heatmap: [GREY_700, VIRIDIS[0], VIRIDIS[1], VIRIDIS[2], VIRIDIS[3]],
+        no_data: GREY_800,
+    };
+}
+
+impl Default for ChartPalette {
+    fn default() -> Self {
+        Self::STANDARD_LIGHT
+    }
+}
+
+const OKABE_ITO_ORANGE: RGBColor = RGBColor(0xE6, 0x9F, 0x00);
+const OKABE_ITO_SKY_BLUE: RGBColor = RGBColor(0x56, 0xB4, 0xE9);
+const OKABE_ITO_GREEN: RGBColor = RGBColor(0x00, 0x9E, 0x73);
+const OKABE_ITO_YELLOW: RGBColor = RGBColor(0xF0, 0xE4, 0x42);
+const OKABE_ITO_BLUE: RGBColor = RGBColor(0x00, 0x72, 0xB2);
+const OKABE_ITO_VERMILLION: RGBColor = RGBColor(0xD5, 0x5E, 0x00);
+const OKABE_ITO_PURPLE: RGBColor = RGBColor(0xCC, 0x79, 0xA7);
+/// Steps of the viridis colour map, from dark purple to yellow.
+const VIRIDIS: [RGBColor; 4] = [
+    RGBColor(0x44, 0x01, 0x54),
+    RGBColor(0x31, 0x68, 0x8E),
+    RGBColor(0x35, 0xB7, 0x79),
+    RGBColor(0xFD, 0xE7, 0x25),
+];
+
+/// Relative luminance of `color`, from 0 for black to 1 for white.
+fn luminance(RGBColor(r, g, b): RGBColor) -> f64 {
+    let channel = |c: u8| {
+        let c = c as f64 / 255.0;
+        match c <= 0.03928 {
+            true => c / 12.92,
+            false => ((c + 0.055) / 1.055).powf(2.4),
+        }
+    };
+    0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b)
+}
+
+/// Parses a CSS colour as computed styles give them: `#rgb`, `#rrggbb`, or
+/// `rgb()`/`rgba()` with comma or space separated channels. Alpha is
+/// ignored.
+pub fn parse_css_color(value: &str) -> Option<RGBColor> {
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+        return match hex.len() {
+            3 | 4 => {
+                let short = |i: usize| channel(&hex[i..i + 1].repeat(2));
+                Some(RGBColor(short(0)?, short(1)?, short(2)?))
+            }
+            6 | 8 => Some(RGBColor(
+                channel(&hex[0..2])?,
+                channel(&hex[2..4])?,
+                channel(&hex[4..6])?,
+            )),
+            _ => None,
+        };
+    }
+    let channels = value
+        .strip_prefix("rgba(")
+        .or_else(|| value.strip_prefix("rgb("))?
+        .strip_suffix(')')?;
+    let mut channels = channels
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|c| !c.is_empty())
+        .map(|c| c.parse::<f64>().ok().map(|c| c.clamp(0.0, 255.0) as u8));
+    Some(RGBColor(
+        channels.next()??,
+        channels.next()??,
+        channels.next()??,
+    ))
+}
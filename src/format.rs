@@ -0,0 +1,338 @@
+//! Locale-aware formatting of numbers and dates for display: thousands
+//! separators, decimal marks, compact counts such as `12.3k`, and numeric
+//! dates in the locale's order.
+//!
+//! The conventions are built in rather than taken from the browser's `Intl`
+//! API, so charts drawn on the server and in the browser read the same. Words,
+//! like the page's own text, stay in English. Times are written in a time zone
+//! chosen alongside the locale.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+
+/// A set of formatting conventions, picked on the stats page or following
+/// the browser's language.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    #[default]
+    EnUs,
+    EnGb,
+    De,
+    Es,
+    Fr,
+    It,
+    Ja,
+    Nl,
+    Pl,
+    Pt,
+    Ru,
+    Zh,
+    /// ISO 8601 dates and SI digit grouping, whatever the language.
+    Iso,
+}
+
+/// How one [`Locale`] writes numbers and dates.
+struct Conventions {
+    group: &'static str,
+    decimal: char,
+    /// `strftime` patterns for a date, a month and a time of day.
+    date: &'static str,
+    month: &'static str,
+    time: &'static str,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 13] = [
+        Locale::EnUs,
+        Locale::EnGb,
+        Locale::De,
+        Locale::Es,
+        Locale::Fr,
+        Locale::It,
+        Locale::Ja,
+        Locale::Nl,
+        Locale::Pl,
+        Locale::Pt,
+        Locale::Ru,
+        Locale::Zh,
+        Locale::Iso,
+    ];
+
+    /// The locale's BCP 47 language tag, or `iso`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Locale::EnUs => "en-US",
+            Locale::EnGb => "en-GB",
+            Locale::De => "de",
+            Locale::Es => "es",
+            Locale::Fr => "fr",
+            Locale::It => "it",
+            Locale::Ja => "ja",
+            Locale::Nl => "nl",
+            Locale::Pl => "pl",
+            Locale::Pt => "pt",
+            Locale::Ru => "ru",
+            Locale::Zh => "zh",
+            Locale::Iso => "iso",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Locale::EnUs => "English (US)",
+            Locale::EnGb => "English (UK)",
+            Locale::De => "Deutsch",
+            Locale::Es => "Español",
+            Locale::Fr => "Français",
+            Locale::It => "Italiano",
+            Locale::Ja => "日本語",
+            Locale::Nl => "Nederlands",
+            Locale::Pl => "Polski",
+            Locale::Pt => "Português",
+            Locale::Ru => "Русский",
+            Locale::Zh => "中文",
+            Locale::Iso => "ISO 8601",
+        }
+    }
+
+    /// The closest locale to a browser's language tag, e.g. `de-AT` or
+    /// `en-AU`, matched by language. English outside the US follows British
+    /// conventions.
+    pub fn negotiate(tag: &str) -> Option<Locale> {
+        let tag = tag.trim().to_ascii_lowercase().replace('_', "-");
+        let (language, region) = match tag.split_once('-') {
+            Some((language, region)) => (language, Some(region)),
+            None => (tag.as_str(), None),
+        };
+        match language {
+            "en" if region.is_none_or(|region| region == "us") => {
+                Some(Locale::EnUs)
+            }
+            "en" => Some(Locale::EnGb),
+            language => Locale::ALL
+                .into_iter()
+                .find(|locale| locale.as_str() == language),
+        }
+    }
+
+    /// The locale of the first language in an `Accept-Language` header that
+    /// has one, e.g. German for `de-AT,de;q=0.9,en;q=0.8`.
+    pub fn from_accept_language(header: &str) -> Option<Locale> {
+        header
+            .split(',')
+            .filter_map(|item| item.split(';').next())
+            .find_map(Locale::negotiate)
+    }
+
+    fn conventions(self) -> Conventions {
+        let (group, decimal) = match self {
+            Locale::EnUs | Locale::EnGb | Locale::Ja | Locale::Zh => (",", '.'),
+            Locale::De | Locale::Es | Locale::It | Locale::Nl | Locale::Pt => {
+                (".", ',')
+            }
+            Locale::Fr => ("\u{202f}", ','),
+            Locale::Pl | Locale::Ru => ("\u{a0}", ','),
+            Locale::Iso => ("\u{202f}", '.'),
+        };
+        let (date, month) = match self {
+            Locale::EnUs => ("%m/%d/%Y", "%m/%Y"),
+            Locale::EnGb
+            | Locale::Es
+            | Locale::Fr
+            | Locale::It
+            | Locale::Pt => ("%d/%m/%Y", "%m/%Y"),
+            Locale::De | Locale::Pl | Locale::Ru => ("%d.%m.%Y", "%m.%Y"),
+            Locale::Nl => ("%d-%m-%Y", "%m-%Y"),
+            Locale::Ja | Locale::Zh => ("%Y/%m/%d", "%Y/%m"),
+            Locale::Iso => ("%Y-%m-%d", "%Y-%m"),
+        };
+        let time = match self {
+            Locale::EnUs => "%-I:%M %p",
+            _ => "%H:%M",
+        };
+        Conventions {
+            group,
+            decimal,
+            date,
+            month,
+            time,
+        }
+    }
+
+    /// A whole number with thousands separators, e.g. `1,234,567`.
+    pub fn number(self, n: i64) -> String {
+        let digits = n.unsigned_abs().to_string();
+        let sign = if n < 0 { "-" } else { "" };
+        format!("{sign}{}", self.group(&digits))
+    }
+
+    /// A number with thousands separators and `places` decimal places, e.g.
+    /// `1,234.5`.
+    pub fn decimal(self, value: f64, places: usize) -> String {
+        let formatted = format!("{:.places$}", value.abs());
+        let (whole, fraction) = match formatted.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (formatted.as_str(), None),
+        };
+        // no sign on a value that rounds to zero
+        let sign = match value < 0.0 && has_nonzero_digit(&formatted) {
+            true => "-",
+            false => "",
+        };
+        let mut out = format!("{sign}{}", self.group(whole));
+        if let Some(fraction) = fraction {
+            out.push(self.conventions().decimal);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    /// Like [`Locale::decimal`], with a `+` on positive values.
+    pub fn signed(self, value: f64, places: usize) -> String {
+        let formatted = self.decimal(value, places);
+        match value > 0.0 && has_nonzero_digit(&formatted) {
+            true => format!("+{formatted}"),
+            false => formatted,
+        }
+    }
+
+    /// A percentage with `places` decimal places, e.g. `12.5%`.
+    pub fn percent(self, value: f64, places: usize) -> String {
+        format!("{}%", self.decimal(value, places))
+    }
+
+    /// A count shortened to three significant digits with a `k`, `M` or `B`
+    /// suffix, e.g. `12.3k` or `1.2M`. Counts under a thousand are written
+    /// whole.
+    pub fn compact(self, value: f64) -> String {
+        let (unit, suffix) = match value.abs() {
+            n if n >= 999_500_000.0 => (1e9, "B"),
+            n if n >= 999_500.0 => (1e6, "M"),
+            n if n >= 999.5 => (1e3, "k"),
+            _ => return self.number(value.round() as i64),
+        };
+        let scaled = value / unit;
+        let places = match scaled.abs() {
+            n if n < 9.995 => 2,
+            n if n < 99.95 => 1,
+            _ => 0,
+        };
+        let decimal = self.conventions().decimal;
+        let formatted = self.decimal(scaled, places);
+        let formatted = match formatted.contains(decimal) {
+            true => formatted.trim_end_matches('0').trim_end_matches(decimal),
+            false => &formatted,
+        };
+        format!("{formatted}{suffix}")
+    }
+
+    /// A date in the locale's numeric order, e.g. `05/31/2024`.
+    pub fn date(self, date: NaiveDate) -> String {
+        date.format(self.conventions().date).to_string()
+    }
+
+    /// A month and year, e.g. `05/2024`.
+    pub fn month(self, date: NaiveDate) -> String {
+        date.format(self.conventions().month).to_string()
+    }
+
+    /// A date and time of day, e.g. `05/31/2024 3:00 PM`.
+    pub fn date_time(self, time: NaiveDateTime) -> String {
+        let conventions = self.conventions();
+        format!(
+            "{} {}",
+            time.format(conventions.date),
+            time.format(conventions.time)
+        )
+    }
+
+    /// The date in `time_zone` of a unix time in milliseconds, as
+    /// [`Locale::date`].
+    pub fn date_at(self, timestamp_millis: i64, time_zone: Tz) -> String {
+        local_time(timestamp_millis, time_zone)
+            .map(|t| self.date(t.date()))
+            .unwrap_or_default()
+    }
+
+    /// The date and time in `time_zone` of a unix time in milliseconds, as
+    /// [`Locale::date_time`].
+    pub fn date_time_at(self, timestamp_millis: i64, time_zone: Tz) -> String {
+        local_time(timestamp_millis, time_zone)
+            .map(|t| self.date_time(t))
+            .unwrap_or_default()
+    }
+
+    /// `digits` with the locale's separator between each group of three.
+    fn group(self, digits: &str) -> String {
+        let separator = self.conventions().group;
+        let mut out = String::new();
+        for (idx, digit) in digits.chars().enumerate() {
+            if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+                out.push_str(separator);
+            }
+            out.push(digit);
+        }
+        out
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Locale::ALL
+            .into_iter()
+            .find(|locale| locale.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let known: Vec<&str> =
+                    Locale::ALL.iter().map(|locale| locale.as_str()).collect();
+                format!(
+                    "unknown locale '{s}', expected one of {}",
+                    known.join(", ")
+                )
+            })
+    }
+}
+
+/// The wall-clock time in `time_zone` at a unix time in milliseconds.
+fn local_time(timestamp_millis: i64, time_zone: Tz) -> Option<NaiveDateTime> {
+    DateTime::from_timestamp_millis(timestamp_millis)
+        .map(|t| t.with_timezone(&time_zone).naive_local())
+}
+
+fn has_nonzero_digit(formatted: &str) -> bool {
+    formatted.chars().any(|c| ('1'..='9').contains(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_from_accept_language() {
+        let cases = [
+            ("de-AT,de;q=0.9,en;q=0.8", Some(Locale::De)),
+            ("xx, en-AU;q=0.5", Some(Locale::EnGb)),
+            ("en", Some(Locale::EnUs)),
+            ("*", None),
+            ("", None),
+        ];
+
+        for (header, expected) in cases {
+            assert_eq!(
+                Locale::from_accept_language(header),
+                expected,
+                "{header}"
+            );
+        }
+    }
+}
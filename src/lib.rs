@@ -1,17 +1,25 @@
 #![feature(iter_intersperse)]
 
+pub mod analysis;
+pub mod annotations;
 pub mod app;
+pub mod chart;
+pub mod format;
+pub mod milestones;
 mod my_uuid;
 mod param_helper;
-mod requests;
+pub mod requests;
+#[cfg(feature = "ssr")]
+pub mod server;
+pub mod site;
 mod stats_page;
 
 #[cfg(feature = "hydrate")]
 #[wasm_bindgen::prelude::wasm_bindgen]
 pub fn hydrate() {
     use app::*;
-    use leptos::*;
-    logging::log!("Hydrating app...");
+    tracing_wasm::set_as_global_default();
+    tracing::info!("hydrating app");
 
     leptos::mount::hydrate_body(App);
 }
@@ -1,52 +1,126 @@
 #[cfg(feature = "ssr")]
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    use clap::Parser;
+    use fiber_stats_ui_rs::server::cli::{Cli, Command};
+
+    let cli = Cli::parse();
+    cli.options.apply();
+    // checked and reported by `--check-config` instead
+    if let (Some(path), false) =
+        (&cli.options.annotations, cli.options.check_config)
+    {
+        fiber_stats_ui_rs::server::annotations::load(path)?;
+    }
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(cli.options).await,
+        Command::RenderChart(args) => render_chart(args).await,
+        Command::ExportStatic(args) => {
+            let conf = leptos_config(&cli.options);
+            fiber_stats_ui_rs::server::export::export_static(
+                conf.leptos_options,
+                args,
+            )
+            .await
+        }
+    }
+}
+
+/// The Leptos configuration, with the bind address and site root overridden
+/// from the command line.
+#[cfg(feature = "ssr")]
+fn leptos_config(
+    options: &fiber_stats_ui_rs::server::cli::Options,
+) -> leptos::config::ConfFile {
+    let mut conf = leptos::config::get_configuration(None).unwrap();
+    if let Some(bind) = options.bind {
+        conf.leptos_options.site_addr = bind;
+    }
+    if let Some(site_root) = &options.site_root {
+        conf.leptos_options.site_root = site_root.as_str().into();
+    }
+    conf
+}
+
+#[cfg(feature = "ssr")]
+async fn serve(
+    options: fiber_stats_ui_rs::server::cli::Options,
+) -> std::io::Result<()> {
     use actix_files::Files;
     use actix_web::*;
     use fiber_stats_ui_rs::app::*;
+    use fiber_stats_ui_rs::server::{
+        feeds, health, logging, metrics, og_image, rate_limit, sitemap,
+    };
     use leptos::*;
     use leptos_actix::{generate_route_list, LeptosRoutes};
 
-    let conf = leptos::config::get_configuration(None).unwrap();
+    let conf = leptos_config(&options);
     let addr = conf.leptos_options.site_addr;
+
+    if options.check_config {
+        println!("bind address:   {addr}");
+        println!("site root:      {}", conf.leptos_options.site_root);
+        println!("public URL:     {}", options.public_url);
+        println!("API base URL:   {}", options.api_base_url);
+        println!("API mode:       {:?}", options.recording_mode());
+        println!("cache TTLs:     {:?}", options.cache_ttls());
+        println!("rate limits:    {:?}", options.rate_limits());
+        println!("annotations:    {:?}", options.annotations);
+        println!("log filter:     {}", options.log_level);
+        println!("log format:     {:?}", options.log_format);
+
+        let problems = options.validate(&conf.leptos_options.site_root);
+        for problem in &problems {
+            eprintln!("error: {problem}");
+        }
+        return match problems.is_empty() {
+            true => Ok(()),
+            false => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid configuration",
+            )),
+        };
+    }
+
     // Generate the list of routes in your Leptos App
     let routes = generate_route_list(App);
 
-    println!("Running server on {}", &addr);
-    println!("site_root: {}", conf.leptos_options.site_root);
+    tracing::info!(
+        %addr,
+        site_root = %conf.leptos_options.site_root,
+        "starting server"
+    );
+
+    // shared by every worker, so each client has one set of buckets
+    let rate_limiter =
+        web::Data::new(rate_limit::RateLimiter::new(options.rate_limits()));
 
     HttpServer::new(move || {
         let leptos_options = &conf.leptos_options;
         let site_root = &leptos_options.site_root;
 
         App::new()
+            .route("/healthz", web::get().to(health::healthz))
+            .route("/readyz", web::get().to(health::readyz))
+            .route("/metrics", web::get().to(metrics::metrics))
+            .route("/og/{file}", web::get().to(og_image::og_image))
+            .route("/feeds/{file}", web::get().to(feeds::mod_feed))
+            .route("/robots.txt", web::get().to(sitemap::robots_txt))
+            .route("/sitemap.xml", web::get().to(sitemap::sitemap_index))
+            .route("/sitemaps/{page}", web::get().to(sitemap::sitemap_page))
             .route("/api/{tail:.*}", leptos_actix::handle_server_fns())
             .service(Files::new("/pkg", format!("{site_root}/pkg")))
             .service(Files::new("/assets", format!("{site_root}")))
             .leptos_routes(routes.to_owned(), {
                 let leptos_options = leptos_options.clone();
-                move || {
-                    use leptos::prelude::*;
-
-                    view! {
-                        <!DOCTYPE html>
-                        <html lang="en">
-                            <head>
-                                <meta charset="utf-8"/>
-                                <meta
-                                    name="viewport"
-                                    content="width=device-width, initial-scale=1"
-                                />
-                                <AutoReload options=leptos_options.clone()/>
-                                <HydrationScripts options=leptos_options.clone()/>
-                                <leptos_meta::MetaTags/>
-                            </head>
-                            <body>
-                                <App/>
-                            </body>
-                        </html>
-                    }
-                }})
+                move || shell(leptos_options.clone())
+            })
+            .app_data(rate_limiter.clone())
+            .wrap(middleware::from_fn(rate_limit::limit_requests))
+            .wrap(middleware::from_fn(metrics::track_requests))
+            .wrap(middleware::from_fn(logging::request_span))
 
         //.wrap(middleware::Compress::default())
     })
@@ -55,6 +129,60 @@ async fn main() -> std::io::Result<()> {
     .await
 }
 
+/// Fetches a mod's stats and writes its chart, as drawn on the stats page, to
+/// an SVG file.
+#[cfg(feature = "ssr")]
+async fn render_chart(
+    args: fiber_stats_ui_rs::server::cli::RenderChartArgs,
+) -> std::io::Result<()> {
+    use std::io::{Error, ErrorKind, Write};
+
+    use fiber_stats_ui_rs::analysis::gaps;
+    use fiber_stats_ui_rs::annotations::minecraft_releases;
+    use fiber_stats_ui_rs::chart::palette::ChartPalette;
+    use fiber_stats_ui_rs::chart::{mod_stats_svg, ChartOptions};
+    use fiber_stats_ui_rs::requests::mods::get_stats;
+    use fiber_stats_ui_rs::server::annotations;
+
+    let mod_id = args.mod_id.hyphenated().to_string();
+    let stats = get_stats(mod_id.clone()).await.ok_or_else(|| {
+        Error::new(ErrorKind::NotFound, format!("no stats for mod {mod_id}"))
+    })?;
+    let options = ChartOptions {
+        gap_factor: args.gap_factor,
+        bucket: args.bucket,
+        fill: args.fill,
+        mode: args.mode,
+        range: args.range,
+        palette: ChartPalette::new(args.palette, None),
+        locale: args.locale,
+        time_zone: args.time_zone,
+        annotations: minecraft_releases()
+            .into_iter()
+            .chain(annotations::for_mod(&mod_id))
+            .collect(),
+    };
+    let svg = mod_stats_svg(&stats, &options)
+        .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+    for gap in gaps::detect(&stats, options.gap_factor) {
+        tracing::info!(
+            source = gap.source.label(),
+            start = gap.start,
+            end = gap.end,
+            days = gap.days(),
+            "gap in data"
+        );
+    }
+
+    if args.output.as_os_str() == "-" {
+        std::io::stdout().write_all(svg.as_bytes())
+    } else {
+        std::fs::write(&args.output, svg)?;
+        tracing::info!(output = %args.output.display(), "wrote chart");
+        Ok(())
+    }
+}
+
 #[cfg(not(feature = "ssr"))]
 pub fn main() {
     // no client-side main function
@@ -0,0 +1,81 @@
+//! Download milestones: the points at which a series of download counts first
+//! reached round numbers.
+
+use fibermc_sdk::models::TimestampedModStats;
+
+use crate::analysis::sorted_points;
+
+/// The first data point at or past a threshold.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Milestone {
+    pub threshold: i64,
+    /// Unix time in milliseconds of the first data point past `threshold`.
+    pub reached_at: i64,
+    /// Downloads at that data point.
+    pub downloads: i64,
+}
+
+/// 1k, 5k, 10k, 50k, 100k and so on, as far as an `i64` goes.
+pub fn round_thresholds() -> impl Iterator<Item = i64> {
+    (3..=18u32).flat_map(|exp| [1, 5].map(|m| m * 10i64.pow(exp)))
+}
+
+/// Evenly spaced round thresholds (multiples of 1, 2 or 5 times a power of
+/// ten) giving roughly `count` milestones between `first` and `max` downloads,
+/// so a series gets markers at a scale that suits it.
+pub fn scaled_thresholds(
+    first: i64,
+    max: i64,
+    count: usize,
+) -> impl Iterator<Item = i64> {
+    let raw_step = ((max - first).max(1) as f64 / count.max(1) as f64).max(1.0);
+    let magnitude = 10f64.powi(raw_step.log10().floor() as i32);
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| (m * magnitude) as i64)
+        .find(|&step| step as f64 >= raw_step)
+        .unwrap_or((10.0 * magnitude) as i64)
+        .max(1);
+
+    let start = (first / step + 1) * step;
+    (0..)
+        .map(move |i| start + i * step)
+        .take_while(move |&threshold| threshold <= max)
+}
+
+/// The first and largest download counts in a series, by time.
+pub fn download_range(series: &[TimestampedModStats]) -> Option<(i64, i64)> {
+    let first = sorted_points(series).first()?.1;
+    let max = series.iter().map(|s| s.downloads).max()?;
+    Some((first, max))
+}
+
+/// The milestones crossed within `series`, oldest first. Thresholds the series
+/// already starts at or past are left out, since when they were reached isn't
+/// known.
+pub fn milestones(
+    series: &[TimestampedModStats],
+    thresholds: impl IntoIterator<Item = i64>,
+) -> Vec<Milestone> {
+    let points = sorted_points(series);
+    let (Some(&(_, first)), Some(max)) =
+        (points.first(), points.iter().map(|(_, d)| *d).max())
+    else {
+        return Vec::new();
+    };
+
+    thresholds
+        .into_iter()
+        .take_while(|&threshold| threshold <= max)
+        .filter(|&threshold| threshold > first)
+        .filter_map(|threshold| {
+            points.iter().find(|(_, d)| *d >= threshold).map(
+                |&(reached_at, downloads)| Milestone {
+                    threshold,
+                    reached_at,
+                    downloads,
+                },
+            )
+        })
+        .collect()
+}
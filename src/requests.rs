@@ -1,2 +1,5 @@
+pub mod cache;
 pub mod config;
 pub mod mods;
+pub mod recording;
+pub mod response_cache;
@@ -0,0 +1,161 @@
+//! A stale-while-revalidate cache of API responses in the browser's
+//! `localStorage`.
+//!
+//! Entries are keyed by kind and mod id, and carry the time they were fetched
+//! so the page can say how stale the data it is showing is. Bumping
+//! [`CACHE_VERSION`] orphans every existing entry, which is then evicted on
+//! the next write. Outside of the browser reads always miss and writes are
+//! dropped.
+
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+pub const CACHE_VERSION: u32 = 1;
+
+pub const MOD_KIND: &str = "mod";
+pub const STATS_KIND: &str = "stats";
+
+const KEY_PREFIX: &str = "fiber-stats:";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry<T> {
+    pub version: u32,
+    /// Unix time in milliseconds at which `value` was fetched.
+    pub fetched_at: i64,
+    pub value: T,
+}
+
+impl<T> CacheEntry<T> {
+    /// Wraps a value that was just fetched.
+    pub fn fresh(value: T) -> Self {
+        Self {
+            version: CACHE_VERSION,
+            fetched_at: Utc::now().timestamp_millis(),
+            value,
+        }
+    }
+}
+
+fn key(kind: &str, mod_id: &str) -> String {
+    format!("{KEY_PREFIX}v{CACHE_VERSION}:{kind}:{mod_id}")
+}
+
+pub fn read<T: DeserializeOwned>(
+    kind: &str,
+    mod_id: &str,
+) -> Option<CacheEntry<T>> {
+    #[cfg(feature = "hydrate")]
+    {
+        let raw = browser::storage()?.get_item(&key(kind, mod_id)).ok()??;
+        serde_json::from_str::<CacheEntry<T>>(&raw)
+            .ok()
+            .filter(|entry| entry.version == CACHE_VERSION)
+    }
+    #[cfg(not(feature = "hydrate"))]
+    {
+        let _ = key(kind, mod_id);
+        None
+    }
+}
+
+pub fn write<T: Serialize>(kind: &str, mod_id: &str, entry: &CacheEntry<T>) {
+    #[cfg(feature = "hydrate")]
+    {
+        let (Some(storage), Ok(raw)) =
+            (browser::storage(), serde_json::to_string(entry))
+        else {
+            return;
+        };
+        let key = key(kind, mod_id);
+        browser::evict(&storage, &key, raw.len());
+        if storage.set_item(&key, &raw).is_err() {
+            // most likely over quota; make room by dropping everything else
+            browser::evict(&storage, &key, usize::MAX);
+            let _ = storage.set_item(&key, &raw);
+        }
+    }
+    #[cfg(not(feature = "hydrate"))]
+    {
+        let _ = (key(kind, mod_id), entry);
+    }
+}
+
+/// Describes how long ago `fetched_at` was, e.g. "updated 5 minutes ago".
+pub fn describe_age(fetched_at: i64, now: i64) -> String {
+    let minutes = (now - fetched_at).max(0) / 60_000;
+    match minutes {
+        0 => "updated just now".to_string(),
+        1 => "updated 1 minute ago".to_string(),
+        2..=59 => format!("updated {minutes} minutes ago"),
+        60..=119 => "updated 1 hour ago".to_string(),
+        120..=1439 => format!("updated {} hours ago", minutes / 60),
+        1440..=2879 => "updated 1 day ago".to_string(),
+        _ => format!("updated {} days ago", minutes / 1440),
+    }
+}
+
+#[cfg(feature = "hydrate")]
+mod browser {
+    use serde::Deserialize;
+    use web_sys::Storage;
+
+    use super::{CACHE_VERSION, KEY_PREFIX};
+
+    /// Most entries kept before the oldest are evicted.
+    const MAX_ENTRIES: usize = 40;
+    /// Most bytes of serialized entries kept before the oldest are evicted.
+    /// Well under the ~5MB most browsers allow per origin.
+    const MAX_TOTAL_BYTES: usize = 2 * 1024 * 1024;
+
+    pub fn storage() -> Option<Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    #[derive(Deserialize)]
+    struct EntryMeta {
+        fetched_at: i64,
+    }
+
+    /// Drops entries from older cache versions, then the oldest entries until
+    /// an incoming entry of `incoming_bytes` fits the size and count budgets.
+    /// The entry stored under `incoming_key` is always dropped too, since it is
+    /// about to be replaced.
+    pub fn evict(storage: &Storage, incoming_key: &str, incoming_bytes: usize) {
+        let current_prefix = format!("{KEY_PREFIX}v{CACHE_VERSION}:");
+        let len = storage.length().unwrap_or(0);
+        let keys: Vec<String> = (0..len)
+            .filter_map(|i| storage.key(i).ok().flatten())
+            .filter(|key| key.starts_with(KEY_PREFIX))
+            .collect();
+
+        let mut kept = Vec::new();
+        for key in keys {
+            let raw = storage.get_item(&key).ok().flatten();
+            let meta = raw
+                .as_deref()
+                .and_then(|raw| serde_json::from_str::<EntryMeta>(raw).ok());
+            match (meta, raw) {
+                (Some(meta), Some(raw))
+                    if key.starts_with(&current_prefix)
+                        && key != incoming_key =>
+                {
+                    kept.push((meta.fetched_at, raw.len(), key))
+                }
+                _ => {
+                    let _ = storage.remove_item(&key);
+                }
+            }
+        }
+
+        // newest first, so everything past the budget is the oldest
+        kept.sort_by(|a, b| b.0.cmp(&a.0));
+        let mut total_bytes = incoming_bytes;
+        for (idx, (_, bytes, key)) in kept.into_iter().enumerate() {
+            total_bytes = total_bytes.saturating_add(bytes);
+            if idx + 1 >= MAX_ENTRIES || total_bytes > MAX_TOTAL_BYTES {
+                let _ = storage.remove_item(&key);
+            }
+        }
+    }
+}
@@ -1,10 +1,24 @@
 use fibermc_sdk::apis::configuration::Configuration;
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
+
+// pub const DEFAULT_BASE_PATH: &str = "https://www.fibermc.com";
+// pub const DEFAULT_BASE_PATH: &str = "https://dev.fibermc.com";
+pub const DEFAULT_BASE_PATH: &str = "https://localhost:5001";
+
+static BASE_PATH: OnceCell<String> = OnceCell::new();
+
+/// Overrides the FiberMC API base URL. Has no effect once [`REQUEST_CONFIG`]
+/// has been used.
+pub fn set_base_path(base_path: String) {
+    let _ = BASE_PATH.set(base_path);
+}
+
+pub fn base_path() -> &'static str {
+    BASE_PATH.get_or_init(|| DEFAULT_BASE_PATH.to_owned())
+}
 
 pub static REQUEST_CONFIG: Lazy<Configuration> = Lazy::new(|| Configuration {
-    // base_path: "https://www.fibermc.com".to_owned(),
-    // base_path: "https://dev.fibermc.com".to_owned(),
-    base_path: "https://localhost:5001".to_owned(),
+    base_path: base_path().to_owned(),
     user_agent: Some("OpenAPI-Generator/0.0.1/rust".to_owned()),
     client: reqwest::Client::new(),
     basic_auth: None,
@@ -1,11 +1,21 @@
 use std::fmt::Display;
+use std::future::Future;
 use std::panic::Location;
 
 use fibermc_sdk::apis::*;
 use fibermc_sdk::models::*;
-use leptos::logging::{error, log};
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
 use crate::requests::config::REQUEST_CONFIG;
+use crate::requests::recording::exchange;
+use crate::requests::response_cache::{self, ResponseCache};
+
+static MODS: ResponseCache<ModResponse> = ResponseCache::new();
+static MOD_LIST: ResponseCache<Vec<ModResponse>> = ResponseCache::new();
+static STATS: ResponseCache<ModStatsResponse> = ResponseCache::new();
 
 #[track_caller]
 fn log<T, E, Ctx: Display>(
@@ -13,25 +23,97 @@ fn log<T, E, Ctx: Display>(
     result: &Result<T, Error<E>>,
     ctx: Ctx,
 ) {
-    log!("{}({}) [{}] complete", fn_name, ctx, Location::caller());
-    if let Err(err) = result.as_ref() {
-        error!("{}({}) [{}]: {}", fn_name, ctx, Location::caller(), err);
+    let caller = Location::caller();
+    match result {
+        Ok(_) => tracing::debug!(
+            function = fn_name,
+            params = %ctx,
+            %caller,
+            "upstream call complete"
+        ),
+        Err(err) => tracing::error!(
+            function = fn_name,
+            params = %ctx,
+            %caller,
+            error = %err,
+            "upstream call failed"
+        ),
     }
 }
 
+/// Makes an upstream call through the recorder, tracking its latency and
+/// failures when running on the server. `endpoint` keys the call in captures,
+/// so it must stay the same for old captures to replay; metrics are labelled
+/// with `api_fn`, the `mods_api` function called.
+async fn call_api<T, E, F, Fut>(
+    endpoint: &str,
+    api_fn: &str,
+    params: &[(&str, &str)],
+    call: F,
+) -> Result<T, Error<E>>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, Error<E>>>,
+{
+    #[cfg(feature = "ssr")]
+    let _timer = crate::server::metrics::UPSTREAM_REQUEST_DURATION
+        .with_label_values(&[api_fn])
+        .start_timer();
+
+    let result = exchange(endpoint, params, call).await;
+
+    #[cfg(feature = "ssr")]
+    if result.is_err() {
+        crate::server::metrics::UPSTREAM_ERRORS
+            .with_label_values(&[api_fn])
+            .inc();
+    }
+
+    result
+}
+
 pub async fn get_stats(mod_id: String) -> Option<ModStatsResponse> {
+    let ttl = response_cache::ttls().stats;
+    STATS
+        .get_or_fetch(mod_id.clone(), ttl, || fetch_stats(mod_id))
+        .await
+}
+
+pub async fn get_mod(mod_id: String) -> Option<ModResponse> {
+    let ttl = response_cache::ttls().mods;
+    MODS.get_or_fetch(mod_id.clone(), ttl, || fetch_mod(mod_id))
+        .await
+}
+
+/// Every mod known to FiberMC.
+pub async fn list_mods() -> Option<Vec<ModResponse>> {
+    let ttl = response_cache::ttls().mods;
+    MOD_LIST.get_or_fetch(String::new(), ttl, fetch_mods).await
+}
+
+async fn fetch_stats(mod_id: String) -> Option<ModStatsResponse> {
     let id_str = mod_id.as_str();
-    let result =
-        mods_api::api_v10_mods_id_stats_get(&REQUEST_CONFIG, id_str).await;
+    let result = call_api(
+        "get_stats",
+        "api_v10_mods_id_stats_get",
+        &[("id", id_str)],
+        || mods_api::api_v10_mods_id_stats_get(&REQUEST_CONFIG, id_str),
+    )
+    .await;
 
     log("get_stats", &result, id_str);
 
     result.ok()
 }
 
-pub async fn get_mod(mod_id: String) -> Option<ModResponse> {
+async fn fetch_mod(mod_id: String) -> Option<ModResponse> {
+    let id_str = mod_id.as_str();
     let result =
-        mods_api::api_v10_mods_id_get(&REQUEST_CONFIG, mod_id.as_str()).await;
+        call_api("get_mod", "api_v10_mods_id_get", &[("id", id_str)], || {
+            mods_api::api_v10_mods_id_get(&REQUEST_CONFIG, id_str)
+        })
+        .await;
 
     // there can be an errors at deserialization time that we are throwing away
     // here... There _must_ be a better way to propagate these errors through in
@@ -40,3 +122,31 @@ pub async fn get_mod(mod_id: String) -> Option<ModResponse> {
 
     result.ok()
 }
+
+async fn fetch_mods() -> Option<Vec<ModResponse>> {
+    let result = call_api("list_mods", "api_v10_mods_get", &[], || {
+        mods_api::api_v10_mods_get(&REQUEST_CONFIG)
+    })
+    .await;
+
+    log("list_mods", &result, "");
+
+    result.ok()
+}
+
+/// Fetches a mod's details through the server, so the upstream call can be
+/// cached, recorded and rendered into the initial HTML.
+#[server]
+pub async fn mod_details(
+    mod_id: String,
+) -> Result<Option<ModResponse>, ServerFnError> {
+    Ok(get_mod(mod_id).await)
+}
+
+/// Fetches a mod's download stats through the server. See [`mod_details`].
+#[server]
+pub async fn mod_stats(
+    mod_id: String,
+) -> Result<Option<ModStatsResponse>, ServerFnError> {
+    Ok(get_stats(mod_id).await)
+}
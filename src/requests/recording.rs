@@ -0,0 +1,401 @@
+//! Record-and-replay of upstream FiberMC API traffic.
+//!
+//! In `record` mode every upstream exchange is appended to a JSONL capture
+//! file; in `replay` mode responses are served from that file instead of the
+//! network, in the order they were recorded for each request. A request made
+//! more often than it was recorded fails rather than being served another
+//! response. The mode is picked up from the environment on first use:
+//!
+//! - `FIBER_STATS_API_MODE`: `live` (default), `record` or `replay`
+//! - `FIBER_STATS_API_CAPTURE`: path of the capture file (default
+//!   `requests.jsonl`)
+//!
+//! Recording and replay need file access, so in the browser every call goes
+//! straight to the network.
+
+use std::future::Future;
+use std::path::PathBuf;
+
+use fibermc_sdk::apis::Error;
+use once_cell::sync::OnceCell;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_CAPTURE_PATH: &str = "requests.jsonl";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordingMode {
+    Live,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+impl RecordingMode {
+    pub fn from_env() -> Self {
+        let path = std::env::var("FIBER_STATS_API_CAPTURE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(DEFAULT_CAPTURE_PATH));
+
+        match std::env::var("FIBER_STATS_API_MODE").as_deref() {
+            Ok("record") => RecordingMode::Record(path),
+            Ok("replay") => RecordingMode::Replay(path),
+            _ => RecordingMode::Live,
+        }
+    }
+}
+
+static MODE: OnceCell<RecordingMode> = OnceCell::new();
+
+/// Overrides the mode read from the environment. Has no effect once the first
+/// upstream request has been made.
+pub fn set_mode(mode: RecordingMode) {
+    let _ = MODE.set(mode);
+}
+
+pub fn mode() -> &'static RecordingMode {
+    MODE.get_or_init(RecordingMode::from_env)
+}
+
+/// A single upstream request and its response, as stored in the capture file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedExchange {
+    pub endpoint: String,
+    pub params: Vec<(String, String)>,
+    /// HTTP status of the response, or `0` if no response was received.
+    pub status: u16,
+    /// The deserialized response on success, or the raw error content.
+    pub body: serde_json::Value,
+    pub latency_ms: u64,
+}
+
+impl RecordedExchange {
+    fn matches(&self, endpoint: &str, params: &[(&str, &str)]) -> bool {
+        self.endpoint == endpoint
+            && self.params.len() == params.len()
+            && self
+                .params
+                .iter()
+                .zip(params)
+                .all(|((k1, v1), (k2, v2))| k1 == k2 && v1 == v2)
+    }
+}
+
+/// Runs `call` against the upstream API according to the current
+/// [`RecordingMode`].
+pub async fn exchange<T, E, F, Fut>(
+    endpoint: &str,
+    params: &[(&str, &str)],
+    call: F,
+) -> Result<T, Error<E>>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, Error<E>>>,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        match mode() {
+            RecordingMode::Live => call().await,
+            RecordingMode::Record(path) => {
+                native::record(path, endpoint, params, call).await
+            }
+            RecordingMode::Replay(path) => {
+                native::replay(path, endpoint, params)
+            }
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = (endpoint, params);
+        call().await
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::collections::HashMap;
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, BufRead, BufReader, Write};
+    use std::path::Path;
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    use fibermc_sdk::apis::{Error, ResponseContent};
+
+    use super::*;
+
+    /// Serializes appends so concurrent requests don't interleave lines.
+    static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+    /// The capture being replayed, loaded on the first request.
+    static REPLAY: OnceCell<Replay> = OnceCell::new();
+
+    pub(super) async fn record<T, E, F, Fut>(
+        path: &Path,
+        endpoint: &str,
+        params: &[(&str, &str)],
+        call: F,
+    ) -> Result<T, Error<E>>
+    where
+        T: Serialize,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, Error<E>>>,
+    {
+        let start = Instant::now();
+        let result = call().await;
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        let (status, body) = match &result {
+            Ok(value) => match serde_json::to_value(value) {
+                Ok(body) => (200, body),
+                Err(err) => {
+                    // a `null` body would replay as "no data" rather than
+                    // failing, so leave the exchange out of the capture
+                    tracing::warn!(
+                        endpoint,
+                        error = %err,
+                        "failed to serialize upstream response, not recording it"
+                    );
+                    return result;
+                }
+            },
+            Err(Error::ResponseError(content)) => (
+                content.status.as_u16(),
+                serde_json::Value::String(content.content.clone()),
+            ),
+            Err(err) => (0, serde_json::Value::String(err.to_string())),
+        };
+
+        let entry = RecordedExchange {
+            endpoint: endpoint.to_string(),
+            params: params
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            status,
+            body,
+            latency_ms,
+        };
+
+        if let Err(err) = append(path, &entry) {
+            tracing::warn!(
+                endpoint,
+                path = %path.display(),
+                error = %err,
+                "failed to record upstream exchange"
+            );
+        }
+
+        result
+    }
+
+    fn append(path: &Path, entry: &RecordedExchange) -> io::Result<()> {
+        let line = serde_json::to_string(entry)?;
+        let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file =
+            OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{line}")
+    }
+
+    pub(super) fn replay<T, E>(
+        path: &Path,
+        endpoint: &str,
+        params: &[(&str, &str)],
+    ) -> Result<T, Error<E>>
+    where
+        T: DeserializeOwned,
+    {
+        REPLAY
+            .get_or_try_init(|| Replay::load(path))?
+            .next(endpoint, params)
+    }
+
+    /// The exchanges of a capture file, with how many of each request's have
+    /// been served.
+    pub(super) struct Replay {
+        exchanges: Vec<RecordedExchange>,
+        /// Responses served so far, keyed by `endpoint(params)`.
+        cursors: Mutex<HashMap<String, usize>>,
+    }
+
+    impl Replay {
+        pub(super) fn load(path: &Path) -> io::Result<Self> {
+            let exchanges = BufReader::new(File::open(path)?)
+                .lines()
+                .filter(|line| {
+                    line.as_ref().map(|l| !l.trim().is_empty()).unwrap_or(true)
+                })
+                .map(|line| Ok(serde_json::from_str(&line?)?))
+                .collect::<io::Result<_>>()?;
+            Ok(Self {
+                exchanges,
+                cursors: Mutex::default(),
+            })
+        }
+
+        /// The next recorded response to `endpoint` with `params`.
+        pub(super) fn next<T, E>(
+            &self,
+            endpoint: &str,
+            params: &[(&str, &str)],
+        ) -> Result<T, Error<E>>
+        where
+            T: DeserializeOwned,
+        {
+            let key = format!("{endpoint}({params:?})");
+            let entry = {
+                let mut cursors =
+                    self.cursors.lock().unwrap_or_else(|e| e.into_inner());
+                let cursor = cursors.entry(key.clone()).or_default();
+                let entry = self
+                    .exchanges
+                    .iter()
+                    .filter(|entry| entry.matches(endpoint, params))
+                    .nth(*cursor)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("no more recorded responses for {key}"),
+                        )
+                    })?;
+                *cursor += 1;
+                entry
+            };
+
+            match entry.status {
+                200..=299 => Ok(serde_json::from_value(entry.body.clone())?),
+                0 => Err(Error::Io(io::Error::other(
+                    entry.body.as_str().unwrap_or_default().to_string(),
+                ))),
+                status => Err(Error::ResponseError(ResponseContent {
+                    status: reqwest::StatusCode::from_u16(status)
+                        .unwrap_or(reqwest::StatusCode::INTERNAL_SERVER_ERROR),
+                    content: entry
+                        .body
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    entity: None,
+                })),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io, process};
+
+    use fibermc_sdk::apis::ResponseContent;
+    use futures::executor::block_on;
+    use reqwest::StatusCode;
+
+    use super::native::{record, Replay};
+    use super::*;
+
+    type Response = Result<Vec<i64>, Error<()>>;
+
+    /// A capture file of its own for each test, removed when dropped.
+    struct Capture(PathBuf);
+
+    impl Capture {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("fiber-stats-{name}-{}.jsonl", process::id()));
+            let _ = fs::remove_file(&path);
+            Self(path)
+        }
+
+        fn record(&self, endpoint: &str, id: &str, response: Response) {
+            let params = [("id", id)];
+            let _ = block_on(record(
+                &self.0,
+                endpoint,
+                &params,
+                move || async move { response },
+            ));
+        }
+
+        fn replay(&self) -> Replay {
+            Replay::load(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for Capture {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn next(replay: &Replay, endpoint: &str, id: &str) -> Response {
+        replay.next(endpoint, &[("id", id)])
+    }
+
+    fn status(status: StatusCode, content: &str) -> Response {
+        Err(Error::ResponseError(ResponseContent {
+            status,
+            content: content.to_string(),
+            entity: None,
+        }))
+    }
+
+    #[test]
+    fn replays_responses_in_order_per_request() {
+        let capture = Capture::new("in-order");
+        capture.record("get_stats", "a", Ok(vec![1]));
+        capture.record("get_mod", "a", Ok(vec![10]));
+        capture.record("get_stats", "a", Ok(vec![2]));
+        capture.record("get_stats", "b", Ok(vec![3]));
+
+        let replay = capture.replay();
+        assert_eq!(next(&replay, "get_stats", "b").unwrap(), vec![3]);
+        assert_eq!(next(&replay, "get_stats", "a").unwrap(), vec![1]);
+        assert_eq!(next(&replay, "get_mod", "a").unwrap(), vec![10]);
+        assert_eq!(next(&replay, "get_stats", "a").unwrap(), vec![2]);
+    }
+
+    #[test]
+    fn replays_error_statuses() {
+        let capture = Capture::new("errors");
+        capture.record("get_mod", "a", status(StatusCode::NOT_FOUND, "gone"));
+        capture.record(
+            "get_mod",
+            "a",
+            Err(Error::Io(io::Error::other("timed out"))),
+        );
+
+        let replay = capture.replay();
+        match next(&replay, "get_mod", "a") {
+            Err(Error::ResponseError(content)) => {
+                assert_eq!(content.status, StatusCode::NOT_FOUND);
+                assert_eq!(content.content, "gone");
+            }
+            other => panic!("expected a 404, got {other:?}"),
+        }
+        match next(&replay, "get_mod", "a") {
+            Err(Error::Io(err)) => {
+                assert!(err.to_string().contains("timed out"))
+            }
+            other => panic!("expected no response, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn running_out_of_responses_is_an_error() {
+        let capture = Capture::new("exhausted");
+        capture.record("get_stats", "a", Ok(vec![1]));
+        capture.record("get_stats", "b", Ok(vec![2]));
+
+        let replay = capture.replay();
+        assert_eq!(next(&replay, "get_stats", "a").unwrap(), vec![1]);
+        for (endpoint, id) in [("get_stats", "a"), ("get_mod", "b")] {
+            match next(&replay, endpoint, id) {
+                Err(Error::Io(err)) => {
+                    assert_eq!(err.kind(), io::ErrorKind::NotFound)
+                }
+                other => panic!("{endpoint}({id}): got {other:?}"),
+            }
+        }
+        assert_eq!(next(&replay, "get_stats", "b").unwrap(), vec![2]);
+    }
+}
@@ -0,0 +1,141 @@
+//! In-memory cache of upstream responses on the server, so repeated renders of
+//! the same mod don't each hit the FiberMC API.
+//!
+//! Each kind of response has its own [`ResponseCache`], and only successful
+//! responses are cached. In the browser every call goes straight through; the
+//! `localStorage` cache in [`super::cache`] covers that side.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::OnceCell;
+
+/// Entries beyond this many in one cache trigger a sweep of the oldest ones.
+const MAX_ENTRIES: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheTtls {
+    /// How long mod details are reused. Zero disables caching them.
+    pub mods: Duration,
+    /// How long download stats are reused. Zero disables caching them.
+    pub stats: Duration,
+}
+
+impl Default for CacheTtls {
+    fn default() -> Self {
+        Self {
+            mods: Duration::from_secs(10 * 60),
+            stats: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+static TTLS: OnceCell<CacheTtls> = OnceCell::new();
+
+/// Overrides the default TTLs. Has no effect once the first cached request has
+/// been made.
+pub fn set_ttls(ttls: CacheTtls) {
+    let _ = TTLS.set(ttls);
+}
+
+pub fn ttls() -> CacheTtls {
+    *TTLS.get_or_init(CacheTtls::default)
+}
+
+/// Successful responses of one kind, keyed by what they were requested for.
+pub struct ResponseCache<T> {
+    entries: Mutex<BTreeMap<String, (Instant, T)>>,
+}
+
+impl<T: Clone> ResponseCache<T> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the value cached under `key` if it is younger than `ttl`,
+    /// otherwise runs `fetch` and caches its result.
+    pub async fn get_or_fetch<F, Fut>(
+        &self,
+        key: String,
+        ttl: Duration,
+        fetch: F,
+    ) -> Option<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Option<T>>,
+    {
+        if cfg!(target_arch = "wasm32") || ttl.is_zero() {
+            return fetch().await;
+        }
+        if let Some(value) = self.get(&key, ttl) {
+            return Some(value);
+        }
+        let value = fetch().await?;
+        self.insert(key, value.clone());
+        Some(value)
+    }
+
+    fn get(&self, key: &str, ttl: Duration) -> Option<T> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let (inserted_at, value) = entries.get(key)?;
+        (inserted_at.elapsed() <= ttl).then(|| value.clone())
+    }
+
+    fn insert(&self, key: String, value: T) {
+        let mut entries =
+            self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= MAX_ENTRIES {
+            let mut by_age: Vec<Instant> =
+                entries.values().map(|(at, _)| *at).collect();
+            by_age.sort_unstable();
+            let cutoff = by_age[by_age.len() / 2];
+            entries.retain(|_, (at, _)| *at > cutoff);
+        }
+        entries.insert(key, (Instant::now(), value));
+    }
+}
+
+impl<T: Clone> Default for ResponseCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::thread;
+
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[test]
+    fn reuses_successful_responses_within_the_ttl() {
+        let cache = ResponseCache::new();
+        let fetches = Cell::new(0);
+        let fetch = |value: Option<i64>| {
+            fetches.set(fetches.get() + 1);
+            async move { value }
+        };
+        let get = |key: &str, ttl: Duration, value: Option<i64>| {
+            block_on(cache.get_or_fetch(key.to_string(), ttl, || fetch(value)))
+        };
+        let minute = Duration::from_secs(60);
+
+        assert_eq!(get("a", minute, Some(1)), Some(1));
+        assert_eq!(get("a", minute, Some(2)), Some(1));
+        assert_eq!(get("b", minute, None), None);
+        assert_eq!(get("b", minute, Some(3)), Some(3));
+        assert_eq!(get("a", Duration::ZERO, Some(4)), Some(4));
+        assert_eq!(fetches.get(), 4);
+
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(get("a", Duration::from_millis(1), Some(5)), Some(5));
+        assert_eq!(fetches.get(), 5);
+    }
+}
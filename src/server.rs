@@ -0,0 +1,26 @@
+pub mod annotations;
+pub mod cli;
+pub mod export;
+pub mod feeds;
+pub mod health;
+pub mod logging;
+pub mod metrics;
+pub mod og_image;
+pub mod rate_limit;
+pub mod sitemap;
+pub mod xml;
+
+use actix_web::http::header::ACCEPT_LANGUAGE;
+use actix_web::HttpRequest;
+
+use crate::format::Locale;
+
+/// The locale of a request's `Accept-Language` header, or the default one
+/// without a header that names one.
+pub fn request_locale(req: &HttpRequest) -> Locale {
+    req.headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Locale::from_accept_language)
+        .unwrap_or_default()
+}
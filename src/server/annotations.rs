@@ -0,0 +1,101 @@
+//! The server's annotations file, a JSON object with annotations for every
+//! mod under `global` and for single mods under `mods`, keyed by mod id:
+//!
+//! ```json
+//! {
+//!     "global": [{ "date": "2024-07-01", "label": "Summer sale" }],
+//!     "mods": {
+//!         "6b0e0b8e-6f5e-4a9c-9d0b-7b7f0c2f3e1a": [
+//!             { "date": "2024-05-01", "label": "v2.0", "description": "Release of v2.0" }
+//!         ]
+//!     }
+//! }
+//! ```
+//!
+//! It is read once at startup. Only JSON is supported, so a `.toml` file is
+//! turned away with an error saying so rather than a JSON syntax error.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::annotations::Annotation;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnnotationsFile {
+    #[serde(default)]
+    pub global: Vec<Annotation>,
+    #[serde(default)]
+    pub mods: HashMap<String, Vec<Annotation>>,
+}
+
+static ANNOTATIONS: OnceCell<AnnotationsFile> = OnceCell::new();
+
+/// Reads and checks the annotations file at `path`, with its mod ids
+/// normalized to hyphenated form.
+pub fn read(path: &Path) -> io::Result<AnnotationsFile> {
+    let invalid = |message: String| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("annotations file {}: {message}", path.display()),
+        )
+    };
+
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+    {
+        return Err(invalid("TOML isn't supported, use JSON".to_string()));
+    }
+
+    let file: AnnotationsFile = serde_json::from_slice(&fs::read(path)?)
+        .map_err(|err| invalid(err.to_string()))?;
+    let mods = file
+        .mods
+        .into_iter()
+        .map(|(mod_id, annotations)| match Uuid::try_parse(&mod_id) {
+            Ok(id) => Ok((id.hyphenated().to_string(), annotations)),
+            Err(err) => {
+                Err(invalid(format!("invalid mod id '{mod_id}': {err}")))
+            }
+        })
+        .collect::<io::Result<_>>()?;
+    Ok(AnnotationsFile {
+        global: file.global,
+        mods,
+    })
+}
+
+/// Loads the annotations served from then on. Has no effect after the first
+/// lookup.
+pub fn load(path: &Path) -> io::Result<()> {
+    let file = read(path)?;
+    tracing::info!(
+        path = %path.display(),
+        global = file.global.len(),
+        mods = file.mods.len(),
+        "loaded annotations"
+    );
+    let _ = ANNOTATIONS.set(file);
+    Ok(())
+}
+
+/// The global annotations followed by the mod's own. Empty if no file was
+/// loaded.
+pub fn for_mod(mod_id: &str) -> Vec<Annotation> {
+    let file = ANNOTATIONS.get_or_init(AnnotationsFile::default);
+    let own = Uuid::try_parse(mod_id)
+        .ok()
+        .and_then(|id| file.mods.get(&id.hyphenated().to_string()));
+    file.global
+        .iter()
+        .chain(own.into_iter().flatten())
+        .cloned()
+        .collect()
+}
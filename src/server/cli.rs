@@ -0,0 +1,341 @@
+//! Command-line interface of the server binary.
+//!
+//! Every option can also be given through the environment variable named in
+//! its help text, so the same binary can be configured either way.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono_tz::Tz;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use uuid::Uuid;
+
+use crate::analysis::gaps::DEFAULT_GAP_FACTOR;
+use crate::analysis::resample::{Bucket, Fill};
+use crate::analysis::transform::DisplayMode;
+use crate::chart::palette::PaletteKind;
+use crate::chart::TimeWindow;
+use crate::format::Locale;
+use crate::requests::config;
+use crate::requests::recording::{self, RecordingMode, DEFAULT_CAPTURE_PATH};
+use crate::requests::response_cache::{self, CacheTtls};
+use crate::server::annotations;
+use crate::server::logging::{self, LogFormat};
+use crate::server::rate_limit::{Budget, RateLimits};
+use crate::site;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Server for the FiberMC mod stats pages")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub options: Options,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Serve the stats pages (the default).
+    Serve,
+    /// Render a mod's downloads chart to an SVG file without starting the
+    /// server.
+    RenderChart(RenderChartArgs),
+    /// Render stats pages, with their data baked in, to static files that can
+    /// be hosted without the server.
+    ExportStatic(ExportStaticArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct RenderChartArgs {
+    /// The mod to render.
+    pub mod_id: Uuid,
+
+    /// Where to write the SVG, or `-` for stdout.
+    #[arg(short, long, default_value = "chart.svg")]
+    pub output: PathBuf,
+
+    /// Intervals over this many times the typical sampling interval are
+    /// drawn as gaps in the data.
+    #[arg(long, default_value_t = DEFAULT_GAP_FACTOR)]
+    pub gap_factor: f64,
+
+    /// Plot the series resampled to `hour`, `day`, `week` or `month` buckets
+    /// rather than at their raw data points.
+    #[arg(long)]
+    pub bucket: Option<Bucket>,
+
+    /// How resampled buckets between data points are filled: `interpolate`
+    /// or `carry-forward`.
+    #[arg(long, default_value_t = Fill::Interpolate, requires = "bucket")]
+    pub fill: Fill,
+
+    /// Plot `cumulative` totals, `incremental` downloads since each previous
+    /// point, or `indexed` growth relative to the first point.
+    #[arg(long, default_value_t = DisplayMode::Cumulative)]
+    pub mode: DisplayMode,
+
+    /// Plot only the window `START..END` (RFC 3339 times), as zoomed to on a
+    /// stats page, rather than the whole history.
+    #[arg(long)]
+    pub range: Option<TimeWindow>,
+
+    /// Draw in the `standard` colours or the `color-blind` safe ones.
+    #[arg(long, default_value_t = PaletteKind::Standard)]
+    pub palette: PaletteKind,
+
+    /// Write dates and numbers the way `LOCALE` does, e.g. `en-GB`, `de` or
+    /// `iso`.
+    #[arg(long, default_value_t = Locale::EnUs)]
+    pub locale: Locale,
+
+    /// Write times, and start days when resampling, in the IANA time zone
+    /// `TIME_ZONE`, e.g. `Europe/Berlin`.
+    #[arg(long, default_value_t = Tz::UTC)]
+    pub time_zone: Tz,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportStaticArgs {
+    /// Mods to export, or `all` for every mod the API lists.
+    #[arg(required = true)]
+    pub mods: Vec<ModSelection>,
+
+    /// Directory to write the site to.
+    #[arg(short, long, default_value = "static-site")]
+    pub output: PathBuf,
+
+    /// Bucket size (`hour`, `day`, `week` or `month`) to resample the
+    /// exported `chart.svg` files to, instead of plotting raw data points.
+    #[arg(long)]
+    pub bucket: Option<Bucket>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModSelection {
+    All,
+    Mod(Uuid),
+}
+
+impl FromStr for ModSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(ModSelection::All),
+            _ => Uuid::try_parse(s)
+                .map(ModSelection::Mod)
+                .map_err(|err| format!("expected a mod id or `all`: {err}")),
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// Address to listen on. Defaults to `site-addr` from the Leptos config.
+    #[arg(long, global = true, env = "LEPTOS_SITE_ADDR")]
+    pub bind: Option<SocketAddr>,
+
+    /// Directory holding the built site (`pkg/` and assets). Defaults to
+    /// `site-root` from the Leptos config.
+    #[arg(long, global = true, env = "LEPTOS_SITE_ROOT")]
+    pub site_root: Option<String>,
+
+    /// Origin the site is publicly reachable at, used for absolute links such
+    /// as social preview images.
+    #[arg(
+        long,
+        global = true,
+        env = "FIBER_STATS_PUBLIC_URL",
+        default_value = site::DEFAULT_PUBLIC_URL
+    )]
+    pub public_url: String,
+
+    /// Base URL of the FiberMC API.
+    #[arg(
+        long,
+        global = true,
+        env = "FIBER_STATS_API_BASE_URL",
+        default_value = config::DEFAULT_BASE_PATH
+    )]
+    pub api_base_url: String,
+
+    /// Whether upstream API calls go to the network, are recorded, or are
+    /// replayed from `--api-capture`.
+    #[arg(
+        long,
+        global = true,
+        env = "FIBER_STATS_API_MODE",
+        value_enum,
+        default_value_t = ApiMode::Live
+    )]
+    pub api_mode: ApiMode,
+
+    /// Capture file used by the `record` and `replay` API modes.
+    #[arg(
+        long,
+        global = true,
+        env = "FIBER_STATS_API_CAPTURE",
+        default_value = DEFAULT_CAPTURE_PATH
+    )]
+    pub api_capture: PathBuf,
+
+    /// Seconds to reuse fetched mod details for. 0 disables caching.
+    #[arg(
+        long,
+        global = true,
+        env = "FIBER_STATS_MOD_CACHE_TTL",
+        default_value_t = CacheTtls::default().mods.as_secs()
+    )]
+    pub mod_cache_ttl: u64,
+
+    /// Seconds to reuse fetched download stats for. 0 disables caching.
+    #[arg(
+        long,
+        global = true,
+        env = "FIBER_STATS_STATS_CACHE_TTL",
+        default_value_t = CacheTtls::default().stats.as_secs()
+    )]
+    pub stats_cache_ttl: u64,
+
+    /// Per-IP limit on page renders, as `PER_MINUTE[:BURST]`. 0 disables.
+    #[arg(
+        long,
+        global = true,
+        env = "FIBER_STATS_PAGE_RATE_LIMIT",
+        default_value = "60:20"
+    )]
+    pub page_rate_limit: Budget,
+
+    /// Per-IP limit on `/api/*` server function calls, as
+    /// `PER_MINUTE[:BURST]`. 0 disables.
+    #[arg(
+        long,
+        global = true,
+        env = "FIBER_STATS_API_RATE_LIMIT",
+        default_value = "240:60"
+    )]
+    pub api_rate_limit: Budget,
+
+    /// Per-IP limit on generated images, as `PER_MINUTE[:BURST]`. 0 disables.
+    #[arg(
+        long,
+        global = true,
+        env = "FIBER_STATS_IMAGE_RATE_LIMIT",
+        default_value = "30:10"
+    )]
+    pub image_rate_limit: Budget,
+
+    /// Identify clients by the `Forwarded`/`X-Forwarded-For` headers. Only
+    /// enable behind a reverse proxy that sets them.
+    #[arg(long, global = true, env = "FIBER_STATS_TRUST_PROXY_HEADERS")]
+    pub trust_proxy_headers: bool,
+
+    /// JSON file of dated events to mark on every mod's chart, or on single
+    /// mods' charts, such as releases and promotions.
+    #[arg(long, global = true, env = "FIBER_STATS_ANNOTATIONS")]
+    pub annotations: Option<PathBuf>,
+
+    /// Log filter, in `RUST_LOG` syntax (e.g. `info` or
+    /// `fiber_stats_ui_rs=debug,info`).
+    #[arg(long, global = true, env = "RUST_LOG", default_value = logging::DEFAULT_FILTER)]
+    pub log_level: String,
+
+    /// Log output format: `pretty` or `json`.
+    #[arg(
+        long,
+        global = true,
+        env = "FIBER_STATS_LOG_FORMAT",
+        default_value = "pretty"
+    )]
+    pub log_format: LogFormat,
+
+    /// Validate the configuration, print it, and exit without serving.
+    #[arg(long, global = true)]
+    pub check_config: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ApiMode {
+    Live,
+    Record,
+    Replay,
+}
+
+impl Options {
+    /// Installs logging and the upstream request settings. Must run before
+    /// the first upstream request is made.
+    pub fn apply(&self) {
+        logging::init(self.log_format, Some(&self.log_level));
+        site::set_public_url(&self.public_url);
+        config::set_base_path(self.api_base_url.clone());
+        recording::set_mode(self.recording_mode());
+        response_cache::set_ttls(self.cache_ttls());
+    }
+
+    pub fn recording_mode(&self) -> RecordingMode {
+        match self.api_mode {
+            ApiMode::Live => RecordingMode::Live,
+            ApiMode::Record => RecordingMode::Record(self.api_capture.clone()),
+            ApiMode::Replay => RecordingMode::Replay(self.api_capture.clone()),
+        }
+    }
+
+    pub fn cache_ttls(&self) -> CacheTtls {
+        CacheTtls {
+            mods: Duration::from_secs(self.mod_cache_ttl),
+            stats: Duration::from_secs(self.stats_cache_ttl),
+        }
+    }
+
+    pub fn rate_limits(&self) -> RateLimits {
+        RateLimits {
+            pages: self.page_rate_limit,
+            api: self.api_rate_limit,
+            images: self.image_rate_limit,
+            trust_proxy_headers: self.trust_proxy_headers,
+        }
+    }
+
+    /// Problems that would keep the server from working as configured.
+    pub fn validate(&self, site_root: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Err(err) = reqwest::Url::parse(&self.public_url) {
+            problems.push(format!(
+                "invalid public URL '{}': {err}",
+                self.public_url
+            ));
+        }
+        if let Err(err) = reqwest::Url::parse(&self.api_base_url) {
+            problems.push(format!(
+                "invalid API base URL '{}': {err}",
+                self.api_base_url
+            ));
+        }
+        if let Err(err) =
+            tracing_subscriber::EnvFilter::try_new(&self.log_level)
+        {
+            problems.push(format!(
+                "invalid log filter '{}': {err}",
+                self.log_level
+            ));
+        }
+        if self.api_mode == ApiMode::Replay && !self.api_capture.exists() {
+            problems.push(format!(
+                "capture file {} does not exist",
+                self.api_capture.display()
+            ));
+        }
+        if let Some(path) = &self.annotations {
+            if let Err(err) = annotations::read(path) {
+                problems.push(err.to_string());
+            }
+        }
+        if !std::path::Path::new(site_root).is_dir() {
+            problems.push(format!("site root {site_root} is not a directory"));
+        }
+        problems
+    }
+}
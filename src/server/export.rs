@@ -0,0 +1,220 @@
+//! Static export of the stats pages, for a mirror on hosting with no backend.
+//!
+//! Pages are rendered in-process through the same SSR pipeline `serve` uses,
+//! so each one has its data serialized into it for hydration and never calls a
+//! server function. Each page is written as `stats/{mod_id}/index.html`, next
+//! to its chart as `chart.svg` and the gaps in its data as `gaps.json`, its
+//! preview card is written to `og/{mod_id}.png` and its milestone feed to
+//! `feeds/{mod_id}.atom`. The built `pkg/` and assets are copied alongside.
+//!
+//! The pages are marked as exported, so in the browser they load another
+//! mod's page in full rather than fetching its data from the missing server.
+
+use std::fs;
+use std::future::Future;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+use actix_web::dev::Service;
+use actix_web::web::Bytes;
+use actix_web::{test, web, App};
+use futures::stream::{self, StreamExt};
+use leptos::config::LeptosOptions;
+use leptos_actix::{generate_route_list, LeptosRoutes};
+
+use crate::analysis::gaps::{self, DEFAULT_GAP_FACTOR};
+use crate::annotations::minecraft_releases;
+use crate::app::{self, shell};
+use crate::chart::{mod_stats_svg, ChartOptions};
+use crate::requests::mods::{get_mod, get_stats, list_mods};
+use crate::server::cli::{ExportStaticArgs, ModSelection};
+use crate::server::{annotations, feeds, og_image};
+use crate::site::{self, feed_path, og_image_path};
+
+/// Mods rendered at once.
+const EXPORT_CONCURRENCY: usize = 4;
+
+pub async fn export_static(
+    leptos_options: LeptosOptions,
+    args: ExportStaticArgs,
+) -> io::Result<()> {
+    let site_root = Path::new(leptos_options.site_root.as_ref()).to_path_buf();
+    let pkg_dir = site_root.join(leptos_options.site_pkg_dir.as_ref());
+    if !pkg_dir.is_dir() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "{} not found; build the site with `cargo leptos build --release` first",
+                pkg_dir.display()
+            ),
+        ));
+    }
+
+    let mod_ids = resolve_mods(&args.mods).await?;
+    tracing::info!(
+        mods = mod_ids.len(),
+        output = %args.output.display(),
+        "exporting static site"
+    );
+
+    site::set_static_export();
+    let routes = generate_route_list(app::App);
+    let service = test::init_service(
+        App::new()
+            .route("/og/{file}", web::get().to(og_image::og_image))
+            .route("/feeds/{file}", web::get().to(feeds::mod_feed))
+            .leptos_routes(routes, {
+                let leptos_options = leptos_options.clone();
+                move || shell(leptos_options.clone())
+            })
+            .app_data(web::Data::new(leptos_options.clone())),
+    )
+    .await;
+    let service = &service;
+    let fetch = move |path: String| async move {
+        let req = test::TestRequest::get().uri(&path).to_request();
+        let res = service.call(req).await.map_err(|err| {
+            Error::new(ErrorKind::Other, format!("GET {path}: {err}"))
+        })?;
+        if !res.status().is_success() {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("GET {path} returned {}", res.status()),
+            ));
+        }
+        test::try_read_body(res).await.map_err(|err| {
+            let err: Box<dyn std::error::Error> = err.into();
+            Error::new(ErrorKind::Other, format!("GET {path}: {err}"))
+        })
+    };
+
+    let chart_options = ChartOptions {
+        bucket: args.bucket,
+        ..Default::default()
+    };
+    let out = args.output.as_path();
+    fs::create_dir_all(out.join("og"))?;
+    fs::create_dir_all(out.join("feeds"))?;
+    fs::write(out.join("index.html"), fetch("/".to_string()).await?)?;
+
+    let exported = stream::iter(&mod_ids)
+        .map(|mod_id| export_mod(&fetch, out, mod_id, &chart_options))
+        .buffer_unordered(EXPORT_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<io::Result<Vec<bool>>>()?
+        .into_iter()
+        .filter(|exported| *exported)
+        .count();
+
+    // served from `/pkg` and `/assets` (the whole site root), as by `serve`
+    copy_dir(&pkg_dir, &out.join("pkg"), None)?;
+    copy_dir(&site_root, &out.join("assets"), Some(&pkg_dir))?;
+
+    tracing::info!(
+        exported,
+        skipped = mod_ids.len() - exported,
+        output = %out.display(),
+        "exported static site"
+    );
+    Ok(())
+}
+
+/// The hyphenated ids of the selected mods, without duplicates.
+async fn resolve_mods(selection: &[ModSelection]) -> io::Result<Vec<String>> {
+    let mut mod_ids: Vec<String> = if selection.contains(&ModSelection::All) {
+        list_mods()
+            .await
+            .ok_or_else(|| Error::new(ErrorKind::Other, "failed to list mods"))?
+            .into_iter()
+            .map(|m| m.id.hyphenated().to_string())
+            .collect()
+    } else {
+        selection
+            .iter()
+            .filter_map(|selected| match selected {
+                ModSelection::Mod(id) => Some(id.hyphenated().to_string()),
+                ModSelection::All => None,
+            })
+            .collect()
+    };
+    mod_ids.sort();
+    mod_ids.dedup();
+    Ok(mod_ids)
+}
+
+/// Writes a mod's page, chart, preview card and feed. Returns `false` if the
+/// API has nothing for the mod, in which case nothing is written.
+async fn export_mod<F, Fut>(
+    fetch: &F,
+    out: &Path,
+    mod_id: &str,
+    chart_options: &ChartOptions,
+) -> io::Result<bool>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = io::Result<Bytes>>,
+{
+    // fetched up front so mods the API has nothing for are skipped, and the
+    // renders below are served from the response cache
+    let (mod_response, stats) = futures::join!(
+        get_mod(mod_id.to_string()),
+        get_stats(mod_id.to_string())
+    );
+    let (Some(_), Some(stats)) = (mod_response, stats) else {
+        tracing::warn!(%mod_id, "no data for mod, skipping");
+        return Ok(false);
+    };
+
+    let page_dir = out.join("stats").join(mod_id);
+    fs::create_dir_all(&page_dir)?;
+    fs::write(
+        page_dir.join("index.html"),
+        fetch(format!("/stats/{mod_id}")).await?,
+    )?;
+    let chart_options = ChartOptions {
+        annotations: minecraft_releases()
+            .into_iter()
+            .chain(annotations::for_mod(mod_id))
+            .collect(),
+        ..chart_options.clone()
+    };
+    match mod_stats_svg(&stats, &chart_options) {
+        Ok(svg) => fs::write(page_dir.join("chart.svg"), svg)?,
+        Err(err) => {
+            tracing::warn!(%mod_id, error = %err, "failed to render chart")
+        }
+    }
+    let gaps = gaps::detect(&stats, DEFAULT_GAP_FACTOR);
+    fs::write(
+        page_dir.join("gaps.json"),
+        serde_json::to_vec_pretty(&gaps)?,
+    )?;
+
+    for path in [og_image_path(mod_id), feed_path(mod_id)] {
+        fs::write(out.join(path.trim_start_matches('/')), fetch(path).await?)?;
+    }
+
+    tracing::debug!(%mod_id, "exported mod");
+    Ok(true)
+}
+
+/// Recursively copies `from` into `to`, leaving out `skip`.
+fn copy_dir(from: &Path, to: &Path, skip: Option<&Path>) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        if Some(path.as_path()) == skip {
+            continue;
+        }
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&path, &target, skip)?;
+        } else {
+            fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
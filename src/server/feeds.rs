@@ -0,0 +1,222 @@
+//! Atom feeds of a mod's download milestones, served from
+//! `/feeds/{mod_id}.atom`.
+//!
+//! There is an entry for each round download count (see
+//! [`round_thresholds`]) the mod's overall downloads, or its downloads on a
+//! single platform, crossed, dated by the first data point past it.
+
+use std::io;
+
+use actix_web::http::header::{ACCEPT_LANGUAGE, CACHE_CONTROL, VARY};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use fibermc_sdk::models::{ModResponse, ModStatsResponse};
+use quick_xml::events::BytesText;
+use uuid::Uuid;
+
+use crate::format::Locale;
+use crate::milestones::{milestones, round_thresholds};
+use crate::requests::mods::{get_mod, get_stats};
+use crate::server::request_locale;
+use crate::server::xml::{w3c_datetime, write_xml};
+use crate::site::{absolute_url, feed_path};
+
+const ATOM_NS: &str = "http://www.w3.org/2005/Atom";
+
+pub async fn mod_feed(
+    req: HttpRequest,
+    file: web::Path<String>,
+) -> HttpResponse {
+    let Some(mod_id) = file
+        .strip_suffix(".atom")
+        .and_then(|id| Uuid::try_parse(id).ok())
+        .map(|id| id.hyphenated().to_string())
+    else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    let (mod_response, stats) =
+        futures::join!(get_mod(mod_id.clone()), get_stats(mod_id.clone()));
+    let (Some(mod_response), Some(stats)) = (mod_response, stats) else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    let locale = request_locale(&req);
+    match write_feed(&mod_id, &mod_response, &stats, locale) {
+        Ok(xml) => HttpResponse::Ok()
+            .content_type("application/atom+xml; charset=utf-8")
+            .insert_header((CACHE_CONTROL, "public, max-age=3600"))
+            .insert_header((VARY, ACCEPT_LANGUAGE))
+            .body(xml),
+        Err(err) => {
+            tracing::error!(%mod_id, error = %err, "failed to write feed");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+struct FeedEntry {
+    id: String,
+    title: String,
+    summary: String,
+    reached_at: DateTime<Utc>,
+}
+
+/// Milestone entries for every series, newest first, with numbers and dates
+/// written for `locale`.
+fn feed_entries(
+    page_url: &str,
+    mod_response: &ModResponse,
+    stats: &ModStatsResponse,
+    locale: Locale,
+) -> Vec<FeedEntry> {
+    let series = [
+        ("overall", None, &stats.overall_stats),
+        ("modrinth", Some("Modrinth"), &stats.modrinth_stats),
+        ("curseforge", Some("CurseForge"), &stats.curse_forge_stats),
+    ];
+
+    let mut entries = Vec::new();
+    for (slug, platform, series) in series {
+        let on_platform =
+            platform.map(|p| format!(" on {p}")).unwrap_or_default();
+        let mut previous: Option<(i64, i64)> = None;
+
+        for milestone in milestones(series, round_thresholds()) {
+            let Some(reached_at) =
+                DateTime::from_timestamp_millis(milestone.reached_at)
+            else {
+                continue;
+            };
+            let label = locale.compact(milestone.threshold as f64);
+
+            let mut summary = format!(
+                "{} had {} downloads{on_platform} on {}.",
+                mod_response.name,
+                locale.number(milestone.downloads),
+                locale.date(reached_at.date_naive()),
+            );
+            if let Some((threshold, at)) = previous {
+                let days = (milestone.reached_at - at) / 86_400_000;
+                summary.push_str(&format!(
+                    " That is {} days after reaching {}.",
+                    locale.number(days),
+                    locale.compact(threshold as f64)
+                ));
+            }
+            previous = Some((milestone.threshold, milestone.reached_at));
+
+            entries.push(FeedEntry {
+                id: format!(
+                    "{page_url}#milestone-{slug}-{}",
+                    milestone.threshold
+                ),
+                title: format!(
+                    "{} reached {label} downloads{on_platform}",
+                    mod_response.name
+                ),
+                summary,
+                reached_at,
+            });
+        }
+    }
+
+    entries.sort_by(|a, b| b.reached_at.cmp(&a.reached_at));
+    entries
+}
+
+fn write_feed(
+    mod_id: &str,
+    mod_response: &ModResponse,
+    stats: &ModStatsResponse,
+    locale: Locale,
+) -> io::Result<Vec<u8>> {
+    let page_url = absolute_url(&format!("/stats/{mod_id}"));
+    let feed_url = absolute_url(&feed_path(mod_id));
+    let entries = feed_entries(&page_url, mod_response, stats, locale);
+
+    // Atom requires an update time even with no entries yet; fall back to the
+    // latest data point
+    let updated = entries
+        .first()
+        .map(|entry| entry.reached_at)
+        .or_else(|| {
+            stats
+                .overall_stats
+                .iter()
+                .filter_map(|s| DateTime::parse_from_rfc3339(&s.timestamp).ok())
+                .max()
+                .map(|latest| latest.with_timezone(&Utc))
+        })
+        .unwrap_or_else(Utc::now);
+
+    write_xml(|writer| {
+        writer
+            .create_element("feed")
+            .with_attribute(("xmlns", ATOM_NS))
+            .write_inner_content(|writer| {
+                writer
+                    .create_element("id")
+                    .write_text_content(BytesText::new(&feed_url))?;
+                writer.create_element("title").write_text_content(
+                    BytesText::new(&format!(
+                        "{} download milestones",
+                        mod_response.name
+                    )),
+                )?;
+                writer.create_element("updated").write_text_content(
+                    BytesText::new(&w3c_datetime(updated)),
+                )?;
+                writer
+                    .create_element("link")
+                    .with_attribute(("rel", "self"))
+                    .with_attribute(("href", feed_url.as_str()))
+                    .write_empty()?;
+                writer
+                    .create_element("link")
+                    .with_attribute(("rel", "alternate"))
+                    .with_attribute(("href", page_url.as_str()))
+                    .write_empty()?;
+                writer.create_element("author").write_inner_content(
+                    |writer| {
+                        writer
+                            .create_element("name")
+                            .write_text_content(BytesText::new("FiberMC"))?;
+                        Ok(())
+                    },
+                )?;
+
+                for entry in &entries {
+                    let date = w3c_datetime(entry.reached_at);
+                    writer.create_element("entry").write_inner_content(
+                        |writer| {
+                            writer.create_element("id").write_text_content(
+                                BytesText::new(&entry.id),
+                            )?;
+                            writer.create_element("title").write_text_content(
+                                BytesText::new(&entry.title),
+                            )?;
+                            writer
+                                .create_element("updated")
+                                .write_text_content(BytesText::new(&date))?;
+                            writer
+                                .create_element("published")
+                                .write_text_content(BytesText::new(&date))?;
+                            writer
+                                .create_element("link")
+                                .with_attribute(("href", page_url.as_str()))
+                                .write_empty()?;
+                            writer
+                                .create_element("summary")
+                                .write_text_content(BytesText::new(
+                                    &entry.summary,
+                                ))?;
+                            Ok(())
+                        },
+                    )?;
+                }
+                Ok(())
+            })?;
+        Ok(())
+    })
+}
@@ -0,0 +1,44 @@
+//! Liveness and readiness probes.
+
+use std::time::Duration;
+
+use actix_web::HttpResponse;
+
+use crate::requests::config::REQUEST_CONFIG;
+use crate::requests::recording::{self, RecordingMode};
+
+const READINESS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The process is up and serving requests.
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().body("ok")
+}
+
+/// The FiberMC API (or, when replaying, the capture file) is reachable, so
+/// stats pages can actually be rendered.
+pub async fn readyz() -> HttpResponse {
+    if let RecordingMode::Replay(path) = recording::mode() {
+        return match path.exists() {
+            true => HttpResponse::Ok().body("ok (replaying)"),
+            false => HttpResponse::ServiceUnavailable()
+                .body(format!("capture {} not found", path.display())),
+        };
+    }
+
+    let result = REQUEST_CONFIG
+        .client
+        .get(&REQUEST_CONFIG.base_path)
+        .timeout(READINESS_TIMEOUT)
+        .send()
+        .await;
+
+    match result {
+        Ok(res) if !res.status().is_server_error() => {
+            HttpResponse::Ok().body("ok")
+        }
+        Ok(res) => HttpResponse::ServiceUnavailable()
+            .body(format!("FiberMC API responded {}", res.status())),
+        Err(err) => HttpResponse::ServiceUnavailable()
+            .body(format!("FiberMC API unreachable: {err}")),
+    }
+}
@@ -0,0 +1,111 @@
+//! Structured logging for the SSR server.
+//!
+//! Every incoming request runs inside an `http_request` span carrying a request
+//! id, so events logged while handling it (including upstream FiberMC API
+//! calls) can be correlated. The id is taken from an incoming `x-request-id`
+//! header when it looks like one, and echoed back on the response.
+
+use std::str::FromStr;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::Error;
+use tracing::Instrument;
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest incoming request id that is kept rather than replaced.
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Filter used when neither `RUST_LOG` nor an explicit level is given.
+pub const DEFAULT_FILTER: &str = "info";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable, multi-line output for local development.
+    #[default]
+    Pretty,
+    /// One JSON object per event, for log aggregation.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("unknown log format '{other}'")),
+        }
+    }
+}
+
+/// Installs the global subscriber. `filter` uses `RUST_LOG` syntax and falls
+/// back to `RUST_LOG` itself, then [`DEFAULT_FILTER`].
+pub fn init(format: LogFormat, filter: Option<&str>) {
+    let filter = filter
+        .map(EnvFilter::new)
+        .or_else(|| EnvFilter::try_from_default_env().ok())
+        .unwrap_or_else(|| EnvFilter::new(DEFAULT_FILTER));
+
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    let result = match format {
+        LogFormat::Pretty => builder.pretty().try_init(),
+        LogFormat::Json => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .try_init(),
+    };
+    if let Err(err) = result {
+        eprintln!("failed to install log subscriber: {err}");
+    }
+}
+
+/// Middleware running each request inside a span tagged with its request id.
+pub async fn request_span(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_owned)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let span = tracing::info_span!(
+        "http_request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.path(),
+    );
+
+    async move {
+        let mut res = next.call(req).await?;
+        tracing::info!(status = res.status().as_u16(), "request handled");
+
+        if let Ok(value) = HeaderValue::from_str(&request_id) {
+            res.headers_mut()
+                .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+        }
+        Ok(res)
+    }
+    .instrument(span)
+    .await
+}
+
+/// Whether a client-supplied request id is safe to log and echo back: a UUID,
+/// or a short run of ASCII letters, digits and hyphens.
+fn is_valid_request_id(id: &str) -> bool {
+    Uuid::try_parse(id).is_ok()
+        || (!id.is_empty()
+            && id.len() <= MAX_REQUEST_ID_LEN
+            && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-'))
+}
@@ -0,0 +1,157 @@
+//! Prometheus metrics for the SSR server, served from `/metrics`.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use actix_web::body::{BodySize, BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::web::Bytes;
+use actix_web::{Error, HttpResponse};
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, Encoder, HistogramTimer,
+    HistogramVec, IntCounterVec, TextEncoder,
+};
+
+pub static HTTP_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "http_requests_total",
+        "HTTP requests handled, by route, method and status",
+        &["route", "method", "status"]
+    )
+    .unwrap()
+});
+
+pub static HTTP_REQUEST_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "http_request_duration_seconds",
+        "Time until the response head is ready, by route and method",
+        &["route", "method"]
+    )
+    .unwrap()
+});
+
+pub static SSR_RENDER_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "ssr_render_duration_seconds",
+        "Time until a server-rendered page has been fully streamed, by route",
+        &["route"]
+    )
+    .unwrap()
+});
+
+pub static UPSTREAM_REQUEST_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "upstream_request_duration_seconds",
+        "Latency of FiberMC API calls, by mods_api function",
+        &["function"]
+    )
+    .unwrap()
+});
+
+pub static UPSTREAM_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "upstream_request_errors_total",
+        "Failed FiberMC API calls, by mods_api function",
+        &["function"]
+    )
+    .unwrap()
+});
+
+pub static RATE_LIMITED_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "rate_limited_requests_total",
+        "Requests rejected by the rate limiter, by route class",
+        &["class"]
+    )
+    .unwrap()
+});
+
+/// Prefixes of the route patterns that are not rendered by Leptos. Static file
+/// services match as their mount path, e.g. `/pkg`, with no trailing slash.
+const NON_PAGE_PREFIXES: &[&str] = &[
+    "/api/",
+    "/pkg",
+    "/assets",
+    "/og/",
+    "/feeds/",
+    "/robots.txt",
+    "/sitemap.xml",
+    "/sitemaps/",
+    "/metrics",
+    "/healthz",
+    "/readyz",
+];
+
+/// Middleware recording request counts and latencies per route, plus the full
+/// streaming time of server-rendered pages.
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    // label by the matched pattern rather than the path, so e.g. every
+    // `/stats/{mod_id}` page shares one series
+    let route = req
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().to_string();
+    let is_page = !NON_PAGE_PREFIXES.iter().any(|p| route.starts_with(p));
+
+    let render_timer = is_page.then(|| {
+        SSR_RENDER_DURATION
+            .with_label_values(&[&route])
+            .start_timer()
+    });
+    let timer = HTTP_REQUEST_DURATION
+        .with_label_values(&[&route, &method])
+        .start_timer();
+
+    let res = next.call(req).await?;
+
+    timer.observe_duration();
+    HTTP_REQUESTS
+        .with_label_values(&[&route, &method, res.status().as_str()])
+        .inc();
+
+    Ok(res.map_body(|_, body| {
+        TimedBody {
+            inner: body.boxed(),
+            _timer: render_timer,
+        }
+        .boxed()
+    }))
+}
+
+/// A response body that observes its timer once it has been sent (or the
+/// client went away).
+struct TimedBody {
+    inner: BoxBody,
+    _timer: Option<HistogramTimer>,
+}
+
+impl MessageBody for TimedBody {
+    type Error = Box<dyn std::error::Error>;
+
+    fn size(&self) -> BodySize {
+        self.inner.size()
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+pub async fn metrics() -> HttpResponse {
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+    match encoder.encode(&prometheus::gather(), &mut buffer) {
+        Ok(()) => HttpResponse::Ok()
+            .content_type(encoder.format_type())
+            .body(buffer),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
@@ -0,0 +1,133 @@
+//! Social preview cards for stats pages, served as PNGs from
+//! `/og/{mod_id}.png` and referenced by the page's `og:image` tag.
+
+use actix_web::http::header::{ACCEPT_LANGUAGE, CACHE_CONTROL, VARY};
+use actix_web::{web, HttpRequest, HttpResponse};
+use fibermc_sdk::models::{ModResponse, ModStatsResponse, TimestampedModStats};
+use image::codecs::png::PngEncoder;
+use image::{ColorType, ImageEncoder};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters::style::full_palette::{BLUE_600, GREY_700};
+use uuid::Uuid;
+
+use crate::chart::{parse_to_timestamp, DrawResult};
+use crate::format::Locale;
+use crate::requests::mods::{get_mod, get_stats};
+use crate::server::request_locale;
+use crate::site::OG_IMAGE_SIZE;
+
+pub async fn og_image(
+    req: HttpRequest,
+    file: web::Path<String>,
+) -> HttpResponse {
+    let Some(mod_id) = file
+        .strip_suffix(".png")
+        .and_then(|id| Uuid::try_parse(id).ok())
+        .map(|id| id.hyphenated().to_string())
+    else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    let (mod_response, stats) =
+        futures::join!(get_mod(mod_id.clone()), get_stats(mod_id.clone()));
+    let (Some(mod_response), Some(stats)) = (mod_response, stats) else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    match render_card(&mod_response, &stats, request_locale(&req)) {
+        Ok(png) => HttpResponse::Ok()
+            .content_type("image/png")
+            .insert_header((CACHE_CONTROL, "public, max-age=3600"))
+            .insert_header((VARY, ACCEPT_LANGUAGE))
+            .body(png),
+        Err(err) => {
+            tracing::error!(%mod_id, error = %err, "failed to render preview card");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Draws the mod's name, total downloads written for `locale` and a sparkline
+/// of its overall downloads, encoded as a PNG.
+pub fn render_card(
+    mod_response: &ModResponse,
+    stats: &ModStatsResponse,
+    locale: Locale,
+) -> DrawResult<Vec<u8>> {
+    let (width, height) = OG_IMAGE_SIZE;
+    let mut pixels = vec![0u8; (width * height * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut pixels, (width, height))
+            .into_drawing_area();
+        root.fill(&WHITE)?;
+        let (header, sparkline) = root.split_vertically(260);
+
+        let total_downloads = stats
+            .overall_stats
+            .iter()
+            .map(|s| s.downloads)
+            .max()
+            .unwrap_or_default();
+
+        header.draw(&Text::new(
+            mod_response.name.clone(),
+            (64, 64),
+            ("sans-serif", 72).into_font().color(&BLACK),
+        ))?;
+        header.draw(&Text::new(
+            format!("{} downloads", locale.number(total_downloads)),
+            (64, 160),
+            ("sans-serif", 44).into_font().color(&GREY_700),
+        ))?;
+        header.draw(&Text::new(
+            "FiberMC mod stats",
+            (width as i32 - 360, 24),
+            ("sans-serif", 28).into_font().color(&GREY_700),
+        ))?;
+
+        draw_sparkline(&sparkline, &stats.overall_stats)?;
+        root.present()?;
+    }
+
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png).write_image(
+        &pixels,
+        width,
+        height,
+        ColorType::Rgb8,
+    )?;
+    Ok(png)
+}
+
+fn draw_sparkline<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    stats: &[TimestampedModStats],
+) -> DrawResult<()>
+where
+    DB::ErrorType: 'static,
+{
+    let points: Vec<(i64, i64)> = stats
+        .iter()
+        .filter_map(|s| Some((parse_to_timestamp(&s.timestamp)?, s.downloads)))
+        .collect();
+    let (Some(min_x), Some(max_x), Some(max_y)) = (
+        points.iter().map(|p| p.0).min(),
+        points.iter().map(|p| p.0).max(),
+        points.iter().map(|p| p.1).max(),
+    ) else {
+        return Ok(());
+    };
+    if min_x == max_x {
+        return Ok(());
+    }
+
+    let mut chart = ChartBuilder::on(area)
+        .margin(64u32)
+        .build_cartesian_2d(min_x..max_x, 0i64..max_y.max(1))?;
+    chart.draw_series(
+        AreaSeries::new(points, 0, BLUE_600.mix(0.2))
+            .border_style(BLUE_600.stroke_width(4)),
+    )?;
+    Ok(())
+}
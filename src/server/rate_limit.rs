@@ -0,0 +1,279 @@
+//! Per-IP token bucket rate limiting.
+//!
+//! Page renders, `/api/*` server functions and image endpoints each have their
+//! own budget, since they fan out to the upstream API very differently. Static
+//! files and probes are never limited. Requests over budget get a `429` with a
+//! `Retry-After` header.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+
+use crate::server::metrics::RATE_LIMITED_REQUESTS;
+
+/// Buckets tracked before idle ones are swept.
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+/// A sustained rate plus how many requests may be made at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Budget {
+    /// Requests per minute. Zero means unlimited.
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+impl Budget {
+    pub const fn new(per_minute: u32, burst: u32) -> Self {
+        Self { per_minute, burst }
+    }
+
+    fn is_unlimited(&self) -> bool {
+        self.per_minute == 0
+    }
+
+    fn tokens_per_sec(&self) -> f64 {
+        self.per_minute as f64 / 60.0
+    }
+}
+
+/// Parses `PER_MINUTE[:BURST]`, e.g. `60:20`. The burst defaults to the
+/// per-minute rate.
+impl FromStr for Budget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<u32>()
+                .map_err(|err| format!("invalid rate limit '{s}': {err}"))
+        };
+        match s.split_once(':') {
+            Some((per_minute, burst)) => {
+                Ok(Budget::new(parse(per_minute)?, parse(burst)?.max(1)))
+            }
+            None => {
+                let per_minute = parse(s)?;
+                Ok(Budget::new(per_minute, per_minute.max(1)))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimits {
+    pub pages: Budget,
+    pub api: Budget,
+    pub images: Budget,
+    /// Take the client address from `Forwarded`/`X-Forwarded-For`. Only safe
+    /// behind a proxy that sets them.
+    pub trust_proxy_headers: bool,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            pages: Budget::new(60, 20),
+            api: Budget::new(240, 60),
+            images: Budget::new(30, 10),
+            trust_proxy_headers: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    Page,
+    Api,
+    Image,
+}
+
+impl RouteClass {
+    /// The budget a path counts against, or `None` if it is never limited.
+    pub fn of(path: &str) -> Option<Self> {
+        const EXEMPT: &[&str] = &[
+            "/pkg/",
+            "/assets/",
+            "/favicon.ico",
+            "/healthz",
+            "/readyz",
+            "/metrics",
+        ];
+        const IMAGES: &[&str] = &["/og/"];
+
+        if EXEMPT.iter().any(|p| path.starts_with(p)) {
+            None
+        } else if path.starts_with("/api/") {
+            Some(RouteClass::Api)
+        } else if IMAGES.iter().any(|p| path.starts_with(p)) {
+            Some(RouteClass::Image)
+        } else {
+            Some(RouteClass::Page)
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            RouteClass::Page => "page",
+            RouteClass::Api => "api",
+            RouteClass::Image => "image",
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+pub struct RateLimiter {
+    limits: RateLimits,
+    buckets: Mutex<HashMap<(IpAddr, RouteClass), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn budget(&self, class: RouteClass) -> Budget {
+        match class {
+            RouteClass::Page => self.limits.pages,
+            RouteClass::Api => self.limits.api,
+            RouteClass::Image => self.limits.images,
+        }
+    }
+
+    /// Takes a token for `ip`, or returns how long until one is available.
+    pub fn check(&self, ip: IpAddr, class: RouteClass) -> Result<(), Duration> {
+        let budget = self.budget(class);
+        if budget.is_unlimited() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets =
+            self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= MAX_TRACKED_BUCKETS {
+            self.sweep(&mut buckets, now);
+        }
+
+        let bucket = buckets.entry((ip, class)).or_insert(Bucket {
+            tokens: budget.burst as f64,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * budget.tokens_per_sec())
+            .min(budget.burst as f64);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = (1.0 - bucket.tokens) / budget.tokens_per_sec();
+            Err(Duration::from_secs_f64(wait))
+        }
+    }
+
+    /// Drops buckets that would have refilled completely by now, as they are
+    /// indistinguishable from new ones.
+    fn sweep(
+        &self,
+        buckets: &mut HashMap<(IpAddr, RouteClass), Bucket>,
+        now: Instant,
+    ) {
+        buckets.retain(|(_, class), bucket| {
+            let budget = self.budget(*class);
+            let refilled = bucket.tokens
+                + now.duration_since(bucket.updated).as_secs_f64()
+                    * budget.tokens_per_sec();
+            refilled < budget.burst as f64
+        });
+    }
+}
+
+/// The IP of a client address from the proxy headers, which may come with a
+/// port, e.g. `203.0.113.7:51234` or `[2001:db8::1]:443`.
+fn client_ip(addr: &str) -> Option<IpAddr> {
+    addr.parse::<SocketAddr>()
+        .map(|addr| addr.ip())
+        .or_else(|_| addr.trim_matches(['[', ']']).parse())
+        .ok()
+}
+
+/// Middleware applying the [`RateLimiter`] registered as app data.
+pub async fn limit_requests(
+    limiter: web::Data<RateLimiter>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(class) = RouteClass::of(req.path()) else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+
+    let ip = match limiter.limits.trust_proxy_headers {
+        true => req
+            .connection_info()
+            .realip_remote_addr()
+            .and_then(client_ip),
+        false => None,
+    }
+    .or_else(|| req.peer_addr().map(|addr| addr.ip()));
+    let Some(ip) = ip else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+
+    match limiter.check(ip, class) {
+        Ok(()) => Ok(next.call(req).await?.map_into_boxed_body()),
+        Err(retry_after) => {
+            RATE_LIMITED_REQUESTS
+                .with_label_values(&[class.label()])
+                .inc();
+            tracing::debug!(%ip, class = class.label(), "rate limited");
+
+            // round up, so clients never retry a moment too early
+            let secs = retry_after.as_secs() + 1;
+            let res = HttpResponse::TooManyRequests()
+                .insert_header((RETRY_AFTER, secs.to_string()))
+                .body("Too many requests, please slow down.");
+            Ok(req.into_response(res))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    #[test]
+    fn client_ip_drops_any_port() {
+        let v4 = IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7));
+        let v6 = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        let cases = [
+            ("203.0.113.7", Some(v4)),
+            ("203.0.113.7:51234", Some(v4)),
+            ("2001:db8::1", Some(v6)),
+            ("[2001:db8::1]", Some(v6)),
+            ("[2001:db8::1]:443", Some(v6)),
+            ("unknown", None),
+            ("", None),
+        ];
+
+        for (addr, expected) in cases {
+            assert_eq!(client_ip(addr), expected, "{addr}");
+        }
+    }
+}
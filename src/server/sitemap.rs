@@ -0,0 +1,151 @@
+//! `/robots.txt` and sitemaps listing every mod's stats page.
+//!
+//! `/sitemap.xml` is a sitemap index pointing at `/sitemaps/{page}.xml`, each
+//! listing up to [`URLS_PER_SITEMAP`] stats pages. Every listed page needs a
+//! stats lookup for its `lastmod`, so keeping pages small keeps each request's
+//! upstream fan-out bounded.
+
+use std::io;
+
+use actix_web::http::header::CACHE_CONTROL;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use fibermc_sdk::models::{ModResponse, ModStatsResponse};
+use futures::StreamExt;
+use quick_xml::events::BytesText;
+
+use crate::requests::mods::{get_stats, list_mods};
+use crate::server::xml::{w3c_datetime, write_xml};
+use crate::site::absolute_url;
+
+pub const URLS_PER_SITEMAP: usize = 1000;
+
+const SITEMAP_NS: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+/// Stats lookups made concurrently while building one sitemap page.
+const STATS_CONCURRENCY: usize = 8;
+
+pub async fn robots_txt() -> HttpResponse {
+    let body = format!(
+        "User-agent: *\nAllow: /stats/\nDisallow: /api/\n\nSitemap: {}\n",
+        absolute_url("/sitemap.xml")
+    );
+    HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body(body)
+}
+
+pub async fn sitemap_index() -> HttpResponse {
+    let Some(mods) = list_mods().await else {
+        return HttpResponse::ServiceUnavailable().finish();
+    };
+    let pages = mods.len().div_ceil(URLS_PER_SITEMAP).max(1);
+
+    xml_response(write_xml(|writer| {
+        writer
+            .create_element("sitemapindex")
+            .with_attribute(("xmlns", SITEMAP_NS))
+            .write_inner_content(|writer| {
+                for page in 0..pages {
+                    writer.create_element("sitemap").write_inner_content(
+                        |writer| {
+                            let loc =
+                                absolute_url(&format!("/sitemaps/{page}.xml"));
+                            writer
+                                .create_element("loc")
+                                .write_text_content(BytesText::new(&loc))?;
+                            Ok(())
+                        },
+                    )?;
+                }
+                Ok(())
+            })?;
+        Ok(())
+    }))
+}
+
+pub async fn sitemap_page(page: web::Path<String>) -> HttpResponse {
+    let Some(page) = page
+        .strip_suffix(".xml")
+        .and_then(|page| page.parse::<usize>().ok())
+    else {
+        return HttpResponse::NotFound().finish();
+    };
+    let Some(mut mods) = list_mods().await else {
+        return HttpResponse::ServiceUnavailable().finish();
+    };
+
+    // a stable order, so a mod stays on the same page between requests
+    mods.sort_by_key(|m| m.id);
+    let page_mods: Vec<ModResponse> = mods
+        .into_iter()
+        .skip(page * URLS_PER_SITEMAP)
+        .take(URLS_PER_SITEMAP)
+        .collect();
+    if page_mods.is_empty() && page > 0 {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let entries: Vec<(String, Option<String>)> =
+        futures::stream::iter(page_mods)
+            .map(|m| async move {
+                let id = m.id.hyphenated().to_string();
+                let lastmod = get_stats(id.clone())
+                    .await
+                    .as_ref()
+                    .and_then(last_modified);
+                (absolute_url(&format!("/stats/{id}")), lastmod)
+            })
+            .buffered(STATS_CONCURRENCY)
+            .collect()
+            .await;
+
+    xml_response(write_xml(|writer| {
+        writer
+            .create_element("urlset")
+            .with_attribute(("xmlns", SITEMAP_NS))
+            .write_inner_content(|writer| {
+                for (loc, lastmod) in &entries {
+                    writer.create_element("url").write_inner_content(
+                        |writer| {
+                            writer
+                                .create_element("loc")
+                                .write_text_content(BytesText::new(loc))?;
+                            if let Some(lastmod) = lastmod {
+                                writer
+                                    .create_element("lastmod")
+                                    .write_text_content(BytesText::new(
+                                        lastmod,
+                                    ))?;
+                            }
+                            Ok(())
+                        },
+                    )?;
+                }
+                Ok(())
+            })?;
+        Ok(())
+    }))
+}
+
+/// The latest stats timestamp, in the W3C datetime format sitemaps expect.
+fn last_modified(stats: &ModStatsResponse) -> Option<String> {
+    stats
+        .overall_stats
+        .iter()
+        .filter_map(|s| DateTime::parse_from_rfc3339(&s.timestamp).ok())
+        .max()
+        .map(|latest| w3c_datetime(latest.with_timezone(&Utc)))
+}
+
+fn xml_response(xml: io::Result<Vec<u8>>) -> HttpResponse {
+    match xml {
+        Ok(xml) => HttpResponse::Ok()
+            .content_type("application/xml; charset=utf-8")
+            .insert_header((CACHE_CONTROL, "public, max-age=3600"))
+            .body(xml),
+        Err(err) => {
+            tracing::error!(error = %err, "failed to write sitemap");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Helpers for the XML documents served alongside the pages (sitemaps and
+//! feeds).
+
+use std::io::{self, Cursor};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use quick_xml::events::{BytesDecl, Event};
+use quick_xml::Writer;
+
+/// Writes an indented document with an XML declaration, its root element
+/// written by `body`.
+pub fn write_xml(
+    body: impl FnOnce(&mut Writer<Cursor<Vec<u8>>>) -> io::Result<()>,
+) -> io::Result<Vec<u8>> {
+    let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new(
+        "1.0",
+        Some("UTF-8"),
+        None,
+    )))?;
+    body(&mut writer)?;
+    Ok(writer.into_inner().into_inner())
+}
+
+/// Formats a time as the RFC 3339 timestamps sitemaps and Atom feeds expect,
+/// e.g. `2024-05-01T12:00:00Z`.
+pub fn w3c_datetime(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,69 @@
+//! Where the site is publicly reachable, for the links that have to be
+//! absolute (social previews, sitemaps, feeds).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use once_cell::sync::OnceCell;
+
+pub const DEFAULT_PUBLIC_URL: &str = "http://127.0.0.1:3000";
+
+/// Size of the generated social preview images, per the Open Graph
+/// recommendation.
+pub const OG_IMAGE_SIZE: (u32, u32) = (1200, 630);
+
+/// Name of the `<meta>` tag marking pages rendered for a static export.
+pub const STATIC_EXPORT_META: &str = "fiber-stats-static-export";
+
+static PUBLIC_URL: OnceCell<String> = OnceCell::new();
+static STATIC_EXPORT: AtomicBool = AtomicBool::new(false);
+
+/// Sets the public origin used on the server. Has no effect after the first
+/// absolute URL has been built.
+pub fn set_public_url(url: &str) {
+    let _ = PUBLIC_URL.set(url.trim_end_matches('/').to_owned());
+}
+
+/// The public origin, e.g. `https://stats.fibermc.com`. In the browser this
+/// is the origin the page was loaded from.
+pub fn public_url() -> String {
+    #[cfg(feature = "hydrate")]
+    if let Some(origin) =
+        web_sys::window().and_then(|w| w.location().origin().ok())
+    {
+        return origin;
+    }
+    PUBLIC_URL
+        .get_or_init(|| DEFAULT_PUBLIC_URL.to_owned())
+        .clone()
+}
+
+/// Marks pages rendered from now on as part of a static export.
+pub fn set_static_export() {
+    STATIC_EXPORT.store(true, Ordering::Relaxed);
+}
+
+/// Whether pages are being rendered for a static export or, in the browser,
+/// the page was loaded from one. Static exports have no server functions.
+pub fn is_static_export() -> bool {
+    #[cfg(feature = "hydrate")]
+    if let Some(document) = web_sys::window().and_then(|w| w.document()) {
+        let selector = format!("meta[name=\"{STATIC_EXPORT_META}\"]");
+        return matches!(document.query_selector(&selector), Ok(Some(_)));
+    }
+    STATIC_EXPORT.load(Ordering::Relaxed)
+}
+
+/// Prefixes a root-relative `path` with the public origin.
+pub fn absolute_url(path: &str) -> String {
+    format!("{}{}", public_url(), path)
+}
+
+/// Path of the social preview image for a mod's stats page.
+pub fn og_image_path(mod_id: &str) -> String {
+    format!("/og/{mod_id}.png")
+}
+
+/// Path of the download milestone feed for a mod's stats page.
+pub fn feed_path(mod_id: &str) -> String {
+    format!("/feeds/{mod_id}.atom")
+}
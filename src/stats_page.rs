@@ -1,27 +1,65 @@
-use std::io::Cursor;
-use std::ops::Range;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
 use std::time::Duration;
 
-use chrono::*;
-use fibermc_sdk::models::{ModResponse, ModStatsResponse, TimestampedModStats};
+use chrono::{DateTime, Datelike, NaiveDate, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use fibermc_sdk::models::{ModResponse, ModStatsResponse};
 use leptos::control_flow::{For, Show};
-use leptos::html::{Div, Script};
-use leptos::logging::log;
+use leptos::html::Script;
 use leptos::prelude::{
-    set_timeout, ElementChild, GlobalAttributes, LocalResource, Memo, NodeRef,
-    NodeRefAttribute, With,
+    event_target, event_target_value, on_cleanup, set_interval_with_handle,
+    window, ClassAttribute, CollectView, Effect, ElementChild, Get,
+    GetUntracked, GlobalAttributes, InnerHtmlAttribute, IntoAny, Memo, NodeRef,
+    NodeRefAttribute, OnAttribute, PropAttribute, Resource, RwSignal, Set,
+    Signal, With,
 };
-use leptos::suspense::Suspense;
+use leptos::reactive::wrappers::write::SignalSetter;
+use leptos::suspense::Transition;
 use leptos::*;
+use leptos_meta::{Link, Meta, Title};
+use leptos_router::hooks::{query_signal_with_options, use_query_map};
 use leptos_router::params::{Params, ParamsError};
-use plotters::prelude::*;
-use plotters::style::full_palette::{BLUE_600, GREEN_600, ORANGE_600};
-use quick_xml::events::Event;
-use quick_xml::Writer;
-use wasm_bindgen::UnwrapThrowExt;
+use leptos_router::NavigateOptions;
 
+use crate::analysis::anomalies::{self, Anomaly};
+use crate::analysis::compare::{
+    compare, last_full_day, Comparison, Period, PeriodPreset, MAX_PERIOD_DAYS,
+};
+use crate::analysis::gaps::{self, Gap, DEFAULT_GAP_FACTOR};
+use crate::analysis::growth::{growth_stats, GrowthStats};
+use crate::analysis::resample::{resample_source, Bucket, Fill};
+use crate::analysis::transform::DisplayMode;
+use crate::analysis::Source;
+use crate::annotations::{
+    self, minecraft_releases, mod_annotations, Annotation,
+};
+use crate::chart::compare::comparison_svg;
+use crate::chart::heatmap::{calendar_heatmap_svg, heatmap_days};
+use crate::chart::navigator::navigator_svg;
+use crate::chart::palette::{ChartPalette, PaletteKind};
+use crate::chart::{
+    downloads_summary, mod_stats_svg, plotted_series, time_bounds,
+    ChartOptions, PlottedSeries, TimeWindow,
+};
+use crate::format::Locale;
 use crate::my_uuid::MyUuid;
-use crate::requests::mods::{get_mod, get_stats};
+use crate::requests::cache::{self, CacheEntry, MOD_KIND, STATS_KIND};
+use crate::requests::mods::{mod_details, mod_stats};
+use crate::site::{
+    self, absolute_url, feed_path, og_image_path, OG_IMAGE_SIZE,
+};
+
+mod interactive_chart;
+mod locale;
+mod theme;
+mod time_zone;
+
+use interactive_chart::{chart_points, InteractiveChart};
+use locale::use_browser_locale;
+use theme::use_theme_colors;
+use time_zone::use_browser_time_zone;
 
 #[derive(Params, PartialEq)]
 pub struct StatsPageParams {
@@ -40,154 +78,458 @@ pub fn StatsPage(
         })
     };
 
-    log!("render, kinda!");
+    tracing::trace!("rendering stats page");
 
     let pretty_mod_id = move || mod_id().ok().map(|id| id.to_pretty_string());
-    let mod_response = LocalResource::new(move || async move {
-        match pretty_mod_id() {
-            Some(id) => get_mod(id).await,
-            None => None,
-        }
+
+    // Previously fetched responses are only read from storage once the page
+    // is running in the browser (effects don't run during SSR or hydration),
+    // so the hydrated markup still matches the server's.
+    let cached_mod = RwSignal::new(None::<CacheEntry<ModResponse>>);
+    let cached_stats = RwSignal::new(None::<CacheEntry<ModStatsResponse>>);
+    Effect::new(move |_| {
+        let id = pretty_mod_id();
+        cached_mod.set(id.as_deref().and_then(|id| cache::read(MOD_KIND, id)));
+        cached_stats
+            .set(id.as_deref().and_then(|id| cache::read(STATS_KIND, id)));
     });
 
-    let stats_response = LocalResource::new(move || async move {
-        match pretty_mod_id() {
-            Some(id) => get_stats(id).await,
-            None => None,
+    // Both are fetched through server functions, so the first render happens
+    // on the server with the data (and social preview tags) already in place.
+    // Each result remembers the id it was fetched for, as a `Transition` keeps
+    // showing the previous mod's data while the next one loads.
+    let mod_response = Resource::new(pretty_mod_id, |id| async move {
+        let entry = match &id {
+            Some(id) if has_server_fns() => {
+                mod_details(id.clone()).await.ok().flatten()
+            }
+            _ => None,
+        };
+        (id, entry.map(CacheEntry::fresh))
+    });
+
+    // the server's annotations file, read before rendering like the stats
+    let annotations_response = Resource::new(pretty_mod_id, |id| async move {
+        match id {
+            Some(id) if has_server_fns() => {
+                mod_annotations(id.clone()).await.unwrap_or_else(|err| {
+                    tracing::error!(
+                        mod_id = %id,
+                        error = %err,
+                        "failed to fetch annotations"
+                    );
+                    Vec::new()
+                })
+            }
+            _ => Vec::new(),
         }
     });
 
-    let ModDownloadsOverTimeView = move || {
-        let stats = move || {
-            stats_response.with(|res| {
-                res.as_ref()
-                    .and_then(|r| r.as_ref())
-                    .map(|r| r.overall_stats.clone())
-            })
+    let stats_response = Resource::new(pretty_mod_id, |id| async move {
+        let entry = match &id {
+            Some(id) if has_server_fns() => {
+                mod_stats(id.clone()).await.ok().flatten()
+            }
+            _ => None,
         };
+        (id, entry.map(CacheEntry::fresh))
+    });
 
-        view! {
-            <Show
-                when=move || stats().is_some()
-                fallback=move || view! { <p>"No stats available"</p> }
-            >
-                <For
-                    each=move || stats().unwrap_or_default()
-                    key=|el| el.timestamp.clone()
-                    let:el
-                >
-                    <div>{format!("({}, {})", el.downloads, el.timestamp)}</div>
-                </For>
-            </Show>
+    // A page loaded from a static export has its own data baked in, but no
+    // server functions to fetch another mod's through, so that mod's exported
+    // page is loaded in full instead.
+    Effect::new(move |loaded: Option<Option<String>>| {
+        let id = pretty_mod_id();
+        if loaded.is_some_and(|loaded| loaded != id) && !has_server_fns() {
+            let _ = window().location().reload();
         }
-    };
+        id
+    });
 
-    let ModOverviewView = move || {
-        mod_response.with(|res| {
-            res.as_ref()
-                .and_then(|res| res.as_ref())
-                .map(|m| view! {<StatsPageModSummary mod_response=m.clone()/>})
-        })
+    Effect::new(move |_| {
+        if let Some((Some(id), Some(entry))) = mod_response.get() {
+            cache::write(MOD_KIND, &id, &entry);
+        }
+    });
+    Effect::new(move |_| {
+        if let Some((Some(id), Some(entry))) = stats_response.get() {
+            cache::write(STATS_KIND, &id, &entry);
+        }
+    });
+
+    // Fresh data for the current mod once it arrives, otherwise whatever was
+    // cached.
+    let current_mod = move || {
+        let id = pretty_mod_id();
+        mod_response
+            .get()
+            .and_then(|(fetched_for, entry)| {
+                entry.filter(|_| fetched_for == id)
+            })
+            .or_else(|| cached_mod.get())
+    };
+    let current_stats = move || {
+        let id = pretty_mod_id();
+        stats_response
+            .get()
+            .and_then(|(fetched_for, entry)| {
+                entry.filter(|_| fetched_for == id)
+            })
+            .or_else(|| cached_stats.get())
     };
+    let is_refreshing = move || {
+        let id = pretty_mod_id();
+        stats_response.get().map(|(fetched_for, _)| fetched_for) != Some(id)
+            && cached_stats.with(Option::is_some)
+    };
+
+    // shared by the chart and the data points list, and kept in the URL like
+    // the other chart options
+    let (bucket, set_bucket) = replace_query::<Bucket>("bucket");
+    let bucket = Signal::from(bucket);
+    let (mode, set_mode) = query_option("mode", DisplayMode::default());
+    // picked on the page, or else the browser's
+    let (picked_locale, set_picked_locale) = replace_query::<Locale>("locale");
+    let picked_locale = Signal::<Option<Locale>>::from(picked_locale);
+    let browser_locale = use_browser_locale();
+    let locale = Signal::derive(move || {
+        picked_locale
+            .get()
+            .or(browser_locale.get())
+            .unwrap_or_default()
+    });
+    let (picked_time_zone, set_picked_time_zone) = replace_query::<Tz>("tz");
+    let picked_time_zone = Signal::<Option<Tz>>::from(picked_time_zone);
+    let browser_time_zone = use_browser_time_zone();
+    let time_zone = Signal::derive(move || {
+        picked_time_zone
+            .get()
+            .or(browser_time_zone.get())
+            .unwrap_or(Tz::UTC)
+    });
 
-    let ModStatsView = move || {
-        let maybe_stats = move || {
-            stats_response
-                .with(|res| res.as_ref().and_then(|msr| msr.as_ref()).cloned())
+    // The page for a mod's details and stats. It's built inside the
+    // `Transition` from the fetched data, and as its fallback from the cached
+    // copies alone, so cached data shows while the first fetch is in flight.
+    let page =
+        move |mod_entry: Signal<Option<CacheEntry<ModResponse>>>,
+              stats_entry: Signal<Option<CacheEntry<ModStatsResponse>>>,
+              annotations: Signal<Vec<Annotation>>,
+              refreshing: Signal<bool>| {
+            let ModDownloadsOverTimeView = move || {
+                let stats = move || {
+                    stats_entry.get().map(|r| {
+                        data_points(&r.value, bucket.get(), time_zone.get())
+                    })
+                };
+
+                view! {
+                    <Show
+                        when=move || stats().is_some()
+                        fallback=move || view! { <p>"No stats available"</p> }
+                    >
+                        <For
+                            each=move || stats().unwrap_or_default()
+                            key=|el| el.1.clone()
+                            let:el
+                        >
+                            <div>{format!("({}, {})", el.0, el.1)}</div>
+                        </For>
+                    </Show>
+                }
+            };
+
+            let ModOverviewView = move || {
+                mod_entry.get().map(|m| {
+                    view! {<StatsPageModSummary mod_response=m.value locale/>}
+                })
+            };
+
+            let ModStatsView = move || {
+                let maybe_stats = move || stats_entry.get();
+
+                view! {
+                    <Show
+                        when=move || maybe_stats().is_some()
+                        fallback=move || view! { <p>"Err!"</p> }
+                    >
+                        <FreshnessIndicator
+                            fetched_at=maybe_stats().unwrap().fetched_at
+                            refreshing=refreshing.get()
+                        />
+                        <ModStatsSection
+                            mod_stats=maybe_stats().unwrap().value
+                            fetched_at=maybe_stats().unwrap().fetched_at
+                            annotations=annotations.get()
+                            bucket
+                            set_bucket
+                            mode
+                            set_mode
+                            locale
+                            picked_locale
+                            set_picked_locale
+                            time_zone
+                            picked_time_zone
+                            set_picked_time_zone
+                        />
+                    </Show>
+                }
+            };
+
+            view! {
+                <Show
+                    when=move || {
+                        mod_entry.with(Option::is_some)
+                            || stats_entry.with(Option::is_some)
+                    }
+                    fallback=move || view! { <p>"Loading..."</p> }
+                >
+                    {ModOverviewView}
+                    <details>
+                        <summary>"View Data Points List"</summary>
+                        <div>{ModDownloadsOverTimeView}</div>
+                    </details>
+                    {ModStatsView}
+                </Show>
+            }
         };
 
-        view! {
-            <Show
-                when=move || maybe_stats().is_some()
-                fallback=move || view! { <p>"Err!"</p> }
-            >
-                <ModStatsSection mod_stats=maybe_stats().unwrap()/>
-            </Show>
+    view! {
+        <Transition
+            fallback=move || page(
+                cached_mod.into(),
+                cached_stats.into(),
+                Signal::derive(Vec::new),
+                Signal::derive(|| true),
+            )
+        >
+            {page(
+                Signal::derive(current_mod),
+                Signal::derive(current_stats),
+                Signal::derive(move || {
+                    annotations_response.get().unwrap_or_default()
+                }),
+                Signal::derive(is_refreshing),
+            )}
+        </Transition>
+    }
+}
+
+/// Whether data can be fetched through server functions: always on the
+/// server, and in the browser unless the page came from a static export.
+fn has_server_fns() -> bool {
+    cfg!(not(feature = "hydrate")) || !site::is_static_export()
+}
+
+/// Says how old the displayed data is, ticking over while the page is open.
+#[component]
+#[allow(non_snake_case)]
+fn FreshnessIndicator(fetched_at: i64, refreshing: bool) -> impl IntoView {
+    let now = RwSignal::new(fetched_at);
+    Effect::new(move |_| {
+        now.set(Utc::now().timestamp_millis());
+        let ticker = set_interval_with_handle(
+            move || now.set(Utc::now().timestamp_millis()),
+            Duration::from_secs(30),
+        );
+        // the indicator is rebuilt on every refresh, so stop this one's timer
+        // along with it
+        if let Ok(ticker) = ticker {
+            on_cleanup(move || ticker.clear());
         }
-    };
+    });
 
     view! {
-        <Suspense
-            fallback=move || view! { <p>"Loading..."</p> }
-        >
-            {ModOverviewView}
-            <details>
-                <summary>"View Data Points List"</summary>
-                <div>{ModDownloadsOverTimeView}</div>
-            </details>
-            {ModStatsView}
-        </Suspense>
+        <p class="freshness">
+            {move || cache::describe_age(fetched_at, now.get())}
+            {refreshing.then_some(" (refreshing...)")}
+        </p>
     }
 }
 
 #[component]
 #[allow(non_snake_case)]
-fn StatsPageModSummary(mod_response: ModResponse) -> impl IntoView {
+fn StatsPageModSummary(
+    mod_response: ModResponse,
+    locale: Signal<Locale>,
+) -> impl IntoView {
+    let download_count = mod_response.download_count;
+    let title = format!("Stats for {}", mod_response.name);
+    let mod_id = mod_response.id.hyphenated().to_string();
+    let page_url = absolute_url(&format!("/stats/{mod_id}"));
+    let image_url = absolute_url(&og_image_path(&mod_id));
+    let feed_title = format!("{} download milestones", mod_response.name);
+
     view! {
-        <title>{format!("Stats for {}", mod_response.name)}</title>
+        <Title text=title.clone()/>
+        <Meta name="description" content=mod_response.summary.clone()/>
+        <Meta property="og:type" content="website"/>
+        <Meta property="og:site_name" content="FiberMC"/>
+        <Meta property="og:title" content=title.clone()/>
+        <Meta property="og:description" content=mod_response.summary.clone()/>
+        <Meta property="og:url" content=page_url/>
+        <Meta property="og:image" content=image_url.clone()/>
+        <Meta property="og:image:width" content=OG_IMAGE_SIZE.0.to_string()/>
+        <Meta property="og:image:height" content=OG_IMAGE_SIZE.1.to_string()/>
+        <Meta name="twitter:card" content="summary_large_image"/>
+        <Meta name="twitter:title" content=title/>
+        <Meta name="twitter:description" content=mod_response.summary.clone()/>
+        <Meta name="twitter:image" content=image_url/>
+        <Link
+            rel="alternate"
+            type_="application/atom+xml"
+            title=feed_title
+            href=feed_path(&mod_id)
+        />
         <h1>"Stats for " {mod_response.name}</h1>
         <div>"("{mod_response.id.hyphenated().to_string()}")"</div>
         <p>{mod_response.summary}</p>
         <div>
             <b>"Downloads: "</b>
-            {mod_response.download_count}
+            {move || locale.get().number(download_count)}
         </div>
     }
 }
 
-fn parse_to_timestamp(s: &str) -> i64 {
-    DateTime::parse_from_rfc3339(s).unwrap().timestamp_millis()
-}
-
-fn parse_to_timestamps(s: &[TimestampedModStats]) -> Vec<i64> {
-    s.iter()
-        .map(|s| s.timestamp.as_str())
-        .map(parse_to_timestamp)
-        .collect()
-}
-
-fn line_series_from_mod_stats<S>(
-    s: &[TimestampedModStats],
-    style: S,
-) -> LineSeries<SVGBackend, (i64, i64)>
-where
-    S: Into<ShapeStyle>,
-{
-    LineSeries::new(
-        s.iter()
-            .cloned()
-            .map(|s| (parse_to_timestamp(&s.timestamp), s.downloads)),
-        style,
-    )
-}
-
 #[component]
 #[allow(non_snake_case)]
-fn ModStatsSection(mod_stats: ModStatsResponse) -> impl IntoView {
-    let container_ref = NodeRef::<Div>::new();
+fn ModStatsSection(
+    mod_stats: ModStatsResponse,
+    fetched_at: i64,
+    annotations: Vec<Annotation>,
+    bucket: Signal<Option<Bucket>>,
+    set_bucket: SignalSetter<Option<Bucket>>,
+    mode: Signal<DisplayMode>,
+    set_mode: SignalSetter<DisplayMode>,
+    locale: Signal<Locale>,
+    picked_locale: Signal<Option<Locale>>,
+    set_picked_locale: SignalSetter<Option<Locale>>,
+    time_zone: Signal<Tz>,
+    picked_time_zone: Signal<Option<Tz>>,
+    set_picked_time_zone: SignalSetter<Option<Tz>>,
+) -> impl IntoView {
     let script_ref = NodeRef::<Script>::new();
+    let query = use_query_map();
 
-    set_timeout(
-        move || render_chart(&mod_stats, container_ref),
-        Duration::from_secs(0),
-    );
+    let anomalies = anomalies::detect(&mod_stats, DEFAULT_GAP_FACTOR);
+    let gaps = gaps::detect(&mod_stats, DEFAULT_GAP_FACTOR);
+    let growth_input = mod_stats.clone();
+    // Days tracked are counted up to when the stats were fetched rather than
+    // the current time, which was taken on the server and comes with the
+    // stats, so hydration works out the same figures. The best day depends on
+    // where days start.
+    let growth = Memo::new(move |_| {
+        growth_stats(&growth_input, fetched_at, time_zone.get())
+    });
+    let heatmap_stats = mod_stats.clone();
+    let comparison_stats = mod_stats.clone();
+    let navigator_stats = mod_stats.clone();
+    let plotted_stats = mod_stats.clone();
+    let summary_stats = mod_stats.clone();
+    let bounds = time_bounds(&mod_stats);
+    // the window shown follows zoom gestures as they happen, while the URL
+    // only gets it once they end, as browsers limit how often it may be
+    // replaced
+    let (committed_range, set_committed_range) =
+        replace_query::<TimeWindow>("range");
+    let range = RwSignal::new(committed_range.get_untracked());
+    Effect::new(move |_| {
+        let committed = committed_range.get();
+        if range.get_untracked() != committed {
+            range.set(committed);
+        }
+    });
+    let commit_range = SignalSetter::map(move |shown| {
+        if committed_range.get_untracked() != shown {
+            set_committed_range.set(shown);
+        }
+    });
+    let (palette_kind, set_palette_kind) =
+        query_option("palette", PaletteKind::default());
+    let theme = use_theme_colors();
+    let palette = Memo::new(move |_| {
+        theme
+            .with(|theme| ChartPalette::new(palette_kind.get(), theme.as_ref()))
+    });
+    let options = Memo::new(move |_| {
+        let shared = query.with(|q| {
+            q.get(annotations::QUERY_PARAM)
+                .map(|value| annotations::parse_query(&value))
+                .unwrap_or_default()
+        });
+        ChartOptions {
+            bucket: bucket.get(),
+            mode: mode.get(),
+            annotations: minecraft_releases()
+                .into_iter()
+                .chain(annotations.iter().cloned())
+                .chain(shared)
+                .collect(),
+            range: range.get(),
+            palette: palette.get(),
+            locale: locale.get(),
+            time_zone: time_zone.get(),
+            ..Default::default()
+        }
+    });
+    // plotters only builds a string, so the chart is drawn during SSR too and
+    // is visible before the wasm bundle loads
+    let chart_svg = Signal::derive(move || {
+        options.with(|options| render_chart(&mod_stats, options))
+    });
+    let plotted = Memo::new(move |_| {
+        options.with(|options| plotted_series(&plotted_stats, options))
+    });
+    let points = Signal::derive(move || {
+        plotted.with(|series| {
+            chart_points(series, mode.get(), locale.get(), time_zone.get())
+        })
+    });
+    let summary = Signal::derive(move || {
+        downloads_summary(
+            &summary_stats,
+            range.get(),
+            locale.get(),
+            time_zone.get(),
+        )
+    });
+    let navigator_svg = Signal::derive(move || {
+        palette.with(|palette| {
+            navigator_svg(
+                &navigator_stats,
+                range.get(),
+                palette,
+                locale.get(),
+                time_zone.get(),
+            )
+            .unwrap_or_else(|err| {
+                tracing::error!(
+                    error = %err,
+                    "failed to render chart navigator"
+                );
+                String::new()
+            })
+        })
+    });
 
-    script_ref.on_load(|el| el.set_src("../assets/js/circles.js"));
+    script_ref.on_load(|el| el.set_src("/assets/js/circles.js"));
+
+    // the charts are drawn in the light palette until the theme's colours
+    // are read in the browser, so on the server, in the static export and
+    // without JavaScript their black text and axes take the theme's colour
+    let theme_pending = move || theme.get().is_none();
 
     view! {
-        <div>
+        <div class:theme_pending=theme_pending>
             <style>
                 """
                 #my_plot {
                     background-color: unset; /*var(--color-base-1);*/
                 }
-                #my_plot text {
+                .theme_pending text[fill=\"#000000\"] {
                     fill: var(--color-text) !important;
                 }
-                #my_plot line {
-                    stroke: var(--color-text) !important;
-                }
-                #my_plot polyline[stroke=\"#000000\"] {
+                .theme_pending [stroke=\"#000000\"] {
                     stroke: var(--color-text) !important;
                 }
                 #my_plot circle::after {
@@ -196,147 +538,994 @@ fn ModStatsSection(mod_stats: ModStatsResponse) -> impl IntoView {
                     height: 32px;
                     background-color: red;
                 }
+                #my_plot_container {
+                    position: relative;
+                    touch-action: none;
+                    user-select: none;
+                }
+                #my_plot_brush {
+                    display: none;
+                    position: absolute;
+                    top: 0;
+                    bottom: 0;
+                    background-color: var(--color-text);
+                    opacity: 0.15;
+                    pointer-events: none;
+                }
+                #my_plot_navigator {
+                    touch-action: none;
+                    user-select: none;
+                    cursor: grab;
+                }
+                #my_plot_container:focus-visible {
+                    outline: 2px solid var(--color-text);
+                }
+                #my_plot circle.focused {
+                    stroke: var(--color-text);
+                    stroke-width: 3;
+                }
+                .visually_hidden {
+                    position: absolute;
+                    width: 1px;
+                    height: 1px;
+                    overflow: hidden;
+                    clip: rect(0 0 0 0);
+                    white-space: nowrap;
+                }
                 #my_plot_tooltip {
+                    display: none;
                     background-color: var(--color-element-1);
                 }
+                .chart_controls {
+                    display: flex;
+                    flex-wrap: wrap;
+                    gap: 1em;
+                }
+                .chart_row {
+                    display: flex;
+                    flex-wrap: wrap;
+                    align-items: flex-start;
+                    gap: 1em;
+                }
+                table.growth td, table.comparison td, table.chart_data td {
+                    text-align: right;
+                }
                 """
             </style>
 
             <script node_ref=script_ref />
             <h3>"Stats"</h3>
-            <div id="my_plot" node_ref=container_ref />
-            <div id="my_plot_tooltip" />
+            <div class="chart_controls">
+                <BucketSelect bucket set_bucket/>
+                <DisplayModeSelect mode set_mode/>
+                <PaletteSelect palette_kind set_palette_kind/>
+                <LocaleSelect picked_locale set_picked_locale/>
+                <TimeZoneSelect picked_time_zone set_picked_time_zone/>
+            </div>
+            <div class="chart_row">
+                {bounds.map(|bounds| view! {
+                    <InteractiveChart
+                        chart_svg
+                        navigator_svg
+                        points
+                        summary
+                        bounds
+                        range
+                        commit_range
+                    />
+                })}
+                {move || view! {
+                    <GrowthPanel
+                        growth=growth.get()
+                        locale=locale.get()
+                        time_zone=time_zone.get()
+                    />
+                }}
+            </div>
+            <ChartDataTable series=plotted mode locale time_zone/>
+            <DownloadsHeatmap mod_stats=heatmap_stats palette locale time_zone/>
+            <PeriodComparison
+                mod_stats=comparison_stats
+                palette
+                locale
+                time_zone
+            />
+            {move || view! {
+                <AnomalyTable
+                    anomalies=anomalies.clone()
+                    locale=locale.get()
+                    time_zone=time_zone.get()
+                />
+            }}
+            {move || view! {
+                <GapTable
+                    gaps=gaps.clone()
+                    locale=locale.get()
+                    time_zone=time_zone.get()
+                />
+            }}
         </div>
     }
 }
 
-fn render_chart(mod_stats: &ModStatsResponse, container_ref: NodeRef<Div>) {
-    if mod_stats.overall_stats.is_empty() {
-        return;
+/// Picks the bucket size the chart and data points list are resampled to.
+#[component]
+#[allow(non_snake_case)]
+fn BucketSelect(
+    bucket: Signal<Option<Bucket>>,
+    set_bucket: SignalSetter<Option<Bucket>>,
+) -> impl IntoView {
+    let options = [(None, "Raw data points")]
+        .into_iter()
+        .chain(Bucket::ALL.map(|b| (Some(b), b.label())));
+
+    view! {
+        <label class="bucket_select">
+            "Resolution "
+            <select on:change=move |ev| {
+                set_bucket.set(event_target_value(&ev).parse().ok())
+            }>
+                {options
+                    .map(|(value, label)| view! {
+                        <option
+                            value=value.map(Bucket::as_str).unwrap_or("raw")
+                            selected=move || bucket.get() == value
+                        >
+                            {label}
+                        </option>
+                    })
+                    .collect_view()}
+            </select>
+        </label>
     }
-    let padding_frac = 0.3f32;
-    let max_downloads = mod_stats
-        .overall_stats
-        .iter()
-        .map(|s| s.downloads)
-        .max()
-        .unwrap();
-    let upper_downloads_axis_bound =
-        ((max_downloads as f32) * (1f32 + padding_frac)) as i64;
-    let timestamps = parse_to_timestamps(&mod_stats.overall_stats);
-    let min_date = *timestamps.iter().min().unwrap();
-    let max_date = *timestamps.iter().max().unwrap();
-
-    let overall_series =
-        line_series_from_mod_stats(&mod_stats.overall_stats, BLUE_600);
-    let modrinth_series =
-        line_series_from_mod_stats(&mod_stats.modrinth_stats, GREEN_600);
-    let curse_series =
-        line_series_from_mod_stats(&mod_stats.curse_forge_stats, ORANGE_600);
-
-    log!(
-        "Overall data points count: {}",
-        &mod_stats.overall_stats.len()
-    );
-    log!(
-        "Modrinth data points count: {}",
-        &mod_stats.modrinth_stats.len()
-    );
-    log!(
-        "CurseForge data points count: {}",
-        &mod_stats.curse_forge_stats.len()
-    );
+}
 
-    let svg_string = draw_series(
-        min_date..max_date,
-        0i64..upper_downloads_axis_bound,
-        vec![overall_series, modrinth_series, curse_series],
-    )
-    .unwrap();
-
-    let mut svg_reader = ::quick_xml::reader::Reader::from_str(&svg_string);
-    let all_points: Vec<TimestampedModStats> = {
-        let mut pts = mod_stats.overall_stats.clone();
-        pts.append(&mut mod_stats.modrinth_stats.clone());
-        pts.append(&mut mod_stats.curse_forge_stats.clone());
-        pts
+/// Picks whether the chart shows totals, new downloads or indexed growth.
+#[component]
+#[allow(non_snake_case)]
+fn DisplayModeSelect(
+    mode: Signal<DisplayMode>,
+    set_mode: SignalSetter<DisplayMode>,
+) -> impl IntoView {
+    view! {
+        <label class="mode_select">
+            "Show "
+            <select on:change=move |ev| {
+                if let Ok(picked) = event_target_value(&ev).parse() {
+                    set_mode.set(picked);
+                }
+            }>
+                {DisplayMode::ALL
+                    .map(|m| view! {
+                        <option value=m.as_str() selected=move || mode.get() == m>
+                            {m.label()}
+                        </option>
+                    })
+                    .collect_view()}
+            </select>
+        </label>
+    }
+}
+
+/// Picks the colours charts are drawn in, for readers who have trouble
+/// telling the standard ones apart.
+#[component]
+#[allow(non_snake_case)]
+fn PaletteSelect(
+    palette_kind: Signal<PaletteKind>,
+    set_palette_kind: SignalSetter<PaletteKind>,
+) -> impl IntoView {
+    view! {
+        <label class="palette_select">
+            "Colours "
+            <select on:change=move |ev| {
+                if let Ok(picked) = event_target_value(&ev).parse() {
+                    set_palette_kind.set(picked);
+                }
+            }>
+                {PaletteKind::ALL
+                    .map(|k| view! {
+                        <option
+                            value=k.as_str()
+                            selected=move || palette_kind.get() == k
+                        >
+                            {k.label()}
+                        </option>
+                    })
+                    .collect_view()}
+            </select>
+        </label>
+    }
+}
+
+/// Picks how dates and numbers are written, following the browser's language
+/// unless one is picked.
+#[component]
+#[allow(non_snake_case)]
+fn LocaleSelect(
+    picked_locale: Signal<Option<Locale>>,
+    set_picked_locale: SignalSetter<Option<Locale>>,
+) -> impl IntoView {
+    let options = [(None, "Browser default")]
+        .into_iter()
+        .chain(Locale::ALL.map(|l| (Some(l), l.label())));
+
+    view! {
+        <label class="locale_select">
+            "Format "
+            <select on:change=move |ev| {
+                set_picked_locale.set(event_target_value(&ev).parse().ok())
+            }>
+                {options
+                    .map(|(value, label)| view! {
+                        <option
+                            value=value.map(Locale::as_str).unwrap_or("browser")
+                            selected=move || picked_locale.get() == value
+                        >
+                            {label}
+                        </option>
+                    })
+                    .collect_view()}
+            </select>
+        </label>
+    }
+}
+
+/// The time zones offered in [`TimeZoneSelect`]; any other is typed in.
+const COMMON_TIME_ZONES: [Tz; 14] = [
+    Tz::UTC,
+    Tz::America__Los_Angeles,
+    Tz::America__Denver,
+    Tz::America__Chicago,
+    Tz::America__New_York,
+    Tz::America__Sao_Paulo,
+    Tz::Europe__London,
+    Tz::Europe__Berlin,
+    Tz::Europe__Moscow,
+    Tz::Asia__Kolkata,
+    Tz::Asia__Shanghai,
+    Tz::Asia__Tokyo,
+    Tz::Australia__Sydney,
+    Tz::Pacific__Auckland,
+];
+
+/// Picks the time zone times are written in and days counted in, following
+/// the browser's unless one is picked. Common zones are listed, and any
+/// other IANA name can be typed in.
+#[component]
+#[allow(non_snake_case)]
+fn TimeZoneSelect(
+    picked_time_zone: Signal<Option<Tz>>,
+    set_picked_time_zone: SignalSetter<Option<Tz>>,
+) -> impl IntoView {
+    // "Other" was picked, but no zone typed in yet
+    let typing = RwSignal::new(false);
+    let invalid = RwSignal::new(false);
+    let other = move || {
+        typing.get()
+            || picked_time_zone
+                .get()
+                .is_some_and(|tz| !COMMON_TIME_ZONES.contains(&tz))
+    };
+    let selected = move || match other() {
+        true => "other",
+        false => picked_time_zone.get().map_or("browser", Tz::name),
     };
 
-    let mut writer = Writer::new(Cursor::new(Vec::new()));
-
-    let mut idx = 0;
-    loop {
-        match svg_reader.read_event() {
-            // exits the loop when reaching end of file
-            Ok(Event::Eof) => break,
-
-            Ok(Event::Empty(e)) if e.name().as_ref() == b"circle" => {
-                log!("circle {}", idx);
-                let point = &all_points[idx];
-                idx += 1;
-                let mut elem = e.into_owned();
-                elem.push_attribute(("data-y", &*point.downloads.to_string()));
-                elem.push_attribute(("data-x", &*point.timestamp.to_string()));
-                assert!(writer.write_event(Event::Empty(elem)).is_ok());
+    view! {
+        <label class="time_zone_select">
+            "Time zone "
+            <select
+                prop:value=selected
+                on:change=move |ev| {
+                    let value = event_target_value(&ev);
+                    typing.set(value == "other");
+                    invalid.set(false);
+                    if value != "other" {
+                        set_picked_time_zone.set(value.parse().ok());
+                    }
+                }
+            >
+                <option value="browser">"Browser default"</option>
+                {COMMON_TIME_ZONES
+                    .map(|tz| {
+                        view! { <option value=tz.name()>{tz.name()}</option> }
+                    })
+                    .collect_view()}
+                <option value="other">"Other"</option>
+            </select>
+        </label>
+        <Show when=other>
+            <label class="time_zone_select">
+                "IANA name "
+                <input
+                    type="text"
+                    placeholder="e.g. Asia/Kathmandu"
+                    aria-invalid=move || invalid.get().to_string()
+                    prop:value=move || {
+                        picked_time_zone.get().map_or("", Tz::name)
+                    }
+                    on:change=move |ev| {
+                        match event_target_value(&ev).trim().parse() {
+                            Ok(tz) => {
+                                invalid.set(false);
+                                typing.set(false);
+                                set_picked_time_zone.set(Some(tz));
+                            }
+                            Err(_) => invalid.set(true),
+                        }
+                    }
+                />
+            </label>
+        </Show>
+    }
+}
+
+/// The points plotted on the downloads chart as a table, one row per time and
+/// one column per source, for readers who can't see the chart. Its rows are
+/// only rendered while it is open, so they stay out of the server's HTML.
+#[component]
+#[allow(non_snake_case)]
+fn ChartDataTable(
+    series: Memo<Vec<PlottedSeries>>,
+    mode: Signal<DisplayMode>,
+    locale: Signal<Locale>,
+    time_zone: Signal<Tz>,
+) -> impl IntoView {
+    let sources = move || {
+        series
+            .with(|series| series.iter().map(|s| s.source).collect::<Vec<_>>())
+    };
+    let open = RwSignal::new(false);
+    let rows = move || {
+        if !open.get() {
+            return BTreeMap::new();
+        }
+        series.with(|series| {
+            let mut rows = BTreeMap::<i64, Vec<Option<f64>>>::new();
+            for (column, plotted) in series.iter().enumerate() {
+                for &(t, value) in plotted.segments.iter().flatten() {
+                    rows.entry(t).or_insert_with(|| vec![None; series.len()])
+                        [column] = Some(value);
+                }
             }
-            Ok(e) => {
-                assert!(writer.write_event(e).is_ok())
+            rows
+        })
+    };
+
+    view! {
+        <details
+            class="chart_data"
+            on:toggle=move |ev| {
+                let details = event_target::<web_sys::Element>(&ev);
+                open.set(details.has_attribute("open"));
             }
-            Err(e) => panic!(
-                "Error at position {}: {:?}",
-                svg_reader.buffer_position(),
-                e
-            ),
-        }
+        >
+            <summary>"Chart data as a table"</summary>
+            <table class="chart_data">
+                <caption>{move || mode.get().label()}</caption>
+                <thead>
+                    <tr>
+                        <th scope="col">
+                            {move || format!("Time ({})", time_zone.get())}
+                        </th>
+                        {move || sources()
+                            .into_iter()
+                            .map(|s| view! { <th scope="col">{s.label()}</th> })
+                            .collect_view()}
+                    </tr>
+                </thead>
+                <tbody>
+                    {move || {
+                        let (mode, locale) = (mode.get(), locale.get());
+                        let time_zone = time_zone.get();
+                        rows()
+                            .into_iter()
+                            .map(|(t, values)| view! {
+                                <tr>
+                                    <th scope="row">
+                                        {locale.date_time_at(t, time_zone)}
+                                    </th>
+                                    {values
+                                        .into_iter()
+                                        .map(|value| view! {
+                                            <td>
+                                                {value
+                                                    .map(|v| mode.point_label(v, locale))
+                                                    .unwrap_or_else(|| "-".to_string())}
+                                            </td>
+                                        })
+                                        .collect_view()}
+                                </tr>
+                            })
+                            .collect_view()
+                    }}
+                </tbody>
+            </table>
+        </details>
     }
-    let svg_blob = writer.into_inner().into_inner();
-    let res = std::str::from_utf8(&svg_blob)
-        .map(|data| data.to_string())
-        .expect_throw(
-            "Failed to generate a download counts svg from the mod_response",
-        );
+}
+
+/// Downloads per day over the last year as a calendar, for a chosen source.
+#[component]
+#[allow(non_snake_case)]
+fn DownloadsHeatmap(
+    mod_stats: ModStatsResponse,
+    palette: Memo<ChartPalette>,
+    locale: Signal<Locale>,
+    time_zone: Signal<Tz>,
+) -> impl IntoView {
+    let (source, set_source) = query_option("heatmap", Source::Overall);
+    let month_stats = mod_stats.clone();
+    let heatmap_svg = move || {
+        palette.with(|palette| {
+            calendar_heatmap_svg(
+                &mod_stats,
+                source.get(),
+                palette,
+                locale.get(),
+                time_zone.get(),
+            )
+            .unwrap_or_else(|err| {
+                tracing::error!(
+                    error = %err,
+                    "failed to render downloads heatmap"
+                );
+                String::new()
+            })
+        })
+    };
+    // the heatmap's days summed by month, as its table alternative, only
+    // built while the table is open
+    let open = RwSignal::new(false);
+    let months = move || {
+        let mut months = BTreeMap::<NaiveDate, (i64, usize)>::new();
+        if !open.get() {
+            return months;
+        }
+        for day in heatmap_days(&month_stats, source.get(), time_zone.get()) {
+            let Some(downloads) = day.downloads else {
+                continue;
+            };
+            let month = day.date.with_day(1).unwrap_or(day.date);
+            let (total, days) = months.entry(month).or_default();
+            *total += downloads;
+            *days += 1;
+        }
+        months
+    };
 
-    container_ref.on_load(move |f| f.set_inner_html(&res));
+    view! {
+        <h4>"Daily downloads"</h4>
+        <label>
+            "Source "
+            <select on:change=move |ev| {
+                if let Ok(picked) = event_target_value(&ev).parse() {
+                    set_source.set(picked);
+                }
+            }>
+                {Source::ALL
+                    .map(|s| view! {
+                        <option
+                            value=s.as_str()
+                            selected=move || source.get() == s
+                        >
+                            {s.label()}
+                        </option>
+                    })
+                    .collect_view()}
+            </select>
+        </label>
+        <div id="my_heatmap" inner_html=heatmap_svg />
+        <details
+            class="chart_data"
+            on:toggle=move |ev| {
+                let details = event_target::<web_sys::Element>(&ev);
+                open.set(details.has_attribute("open"));
+            }
+        >
+            <summary>"Monthly totals as a table"</summary>
+            <table class="chart_data">
+                <thead>
+                    <tr>
+                        <th scope="col">"Month"</th>
+                        <th scope="col">"Downloads"</th>
+                        <th scope="col">"Days with data"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {move || {
+                        let locale = locale.get();
+                        months()
+                            .into_iter()
+                            .map(|(month, (total, days))| view! {
+                                <tr>
+                                    <th scope="row">{locale.month(month)}</th>
+                                    <td>{locale.number(total)}</td>
+                                    <td>{locale.number(days as i64)}</td>
+                                </tr>
+                            })
+                            .collect_view()
+                    }}
+                </tbody>
+            </table>
+        </details>
+    }
 }
 
-/// Type alias for the result of a drawing function.
-pub type DrawResult<T> = Result<T, Box<dyn std::error::Error>>;
-
-pub fn draw_series(
-    x_range: Range<i64>,
-    y_range: Range<i64>,
-    series: Vec<LineSeries<SVGBackend, (i64, i64)>>,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let mut svg_content: String = "".to_string();
-    {
-        let backend = SVGBackend::with_string(&mut svg_content, (800, 600));
-        let root = backend.into_drawing_area();
-        let font: FontDesc = ("sans-serif", 20.0).into();
-
-        let mut chart = ChartBuilder::on(&root)
-            .margin(64u32)
-            .caption("Downloads Over Time", font)
-            .x_label_area_size(30u32)
-            .y_label_area_size(30u32)
-            .build_cartesian_2d(x_range, y_range)?;
-
-        chart
-            .configure_mesh()
-            .x_labels(5)
-            .y_labels(8)
-            .x_label_formatter(&|v| {
-                DateTime::from_timestamp_millis(*v)
-                    .unwrap()
-                    .format("%Y-%m-%d")
-                    .to_string()
+/// Overall daily downloads over two periods, overlaid by day since each
+/// period's start, with a summary of how they differ.
+#[component]
+#[allow(non_snake_case)]
+fn PeriodComparison(
+    mod_stats: ModStatsResponse,
+    palette: Memo<ChartPalette>,
+    locale: Signal<Locale>,
+    time_zone: Signal<Tz>,
+) -> impl IntoView {
+    let last_day = {
+        let mod_stats = mod_stats.clone();
+        Memo::new(move |_| {
+            last_full_day(&mod_stats, Source::Overall, time_zone.get())
+        })
+    };
+    // the query defaults are fixed, so custom periods default to the days up
+    // to the last full day in the time zone the page starts in
+    let Some(initial_last_day) = last_day.get_untracked() else {
+        return ().into_any();
+    };
+    let (default_current, default_previous) =
+        PeriodPreset::ThirtyDays.periods(initial_last_day);
+
+    let (periods, set_periods) =
+        query_option("compare", Periods::Preset(PeriodPreset::ThirtyDays));
+    let (current_start, set_current_start) =
+        query_option("current", default_current.start);
+    let (previous_start, set_previous_start) =
+        query_option("previous", default_previous.start);
+    let (days, set_days) = query_option("days", default_current.days);
+    // the query string is anyone's to write, so keep it to what the input
+    // allows before it reaches `compare`, on the server as in the browser
+    let days = Signal::derive(move || days.get().clamp(1, MAX_PERIOD_DAYS));
+    let is_custom = move || periods.get() == Periods::Custom;
+
+    let comparison = Memo::new(move |_| {
+        let (current, previous) = match periods.get() {
+            Periods::Preset(preset) => {
+                preset.periods(last_day.get().unwrap_or(initial_last_day))
+            }
+            Periods::Custom => (
+                Period {
+                    start: current_start.get(),
+                    days: days.get(),
+                },
+                Period {
+                    start: previous_start.get(),
+                    days: days.get(),
+                },
+            ),
+        };
+        compare(
+            &mod_stats,
+            Source::Overall,
+            current,
+            previous,
+            time_zone.get(),
+        )
+    });
+    let comparison_svg = move || {
+        comparison.with(|comparison| {
+            comparison_svg(comparison, &palette.get(), locale.get()).unwrap_or_else(|err| {
+                tracing::error!(error = %err, "failed to render comparison");
+                String::new()
             })
-            .draw()?;
+        })
+    };
+
+    let date_input =
+        move |date: Signal<NaiveDate>, set_date: SignalSetter<NaiveDate>| {
+            view! {
+                <input
+                    type="date"
+                    prop:value=move || date.get().to_string()
+                    on:change=move |ev| {
+                        if let Ok(picked) = event_target_value(&ev).parse() {
+                            set_date.set(picked);
+                        }
+                    }
+                />
+            }
+        };
+
+    view! {
+        <h4>"Compare periods"</h4>
+        <label>
+            "Periods "
+            <select on:change=move |ev| {
+                if let Ok(picked) = event_target_value(&ev).parse() {
+                    set_periods.set(picked);
+                }
+            }>
+                {PeriodPreset::ALL
+                    .map(|p| view! {
+                        <option
+                            value=p.as_str()
+                            selected=move || periods.get() == Periods::Preset(p)
+                        >
+                            {p.label()}
+                        </option>
+                    })
+                    .collect_view()}
+                <option value=Periods::CUSTOM selected=is_custom>
+                    "Custom periods"
+                </option>
+            </select>
+        </label>
+        <Show when=is_custom>
+            <div class="custom_periods">
+                <label>
+                    "Current from "
+                    {date_input(current_start, set_current_start)}
+                </label>
+                " "
+                <label>
+                    "Previous from "
+                    {date_input(previous_start, set_previous_start)}
+                </label>
+                " "
+                <label>
+                    "Days "
+                    <input
+                        type="number"
+                        min="1"
+                        max=MAX_PERIOD_DAYS.to_string()
+                        prop:value=move || days.get().to_string()
+                        on:change=move |ev| {
+                            if let Some(picked) = event_target_value(&ev)
+                                .parse()
+                                .ok()
+                                .filter(|d| (1..=MAX_PERIOD_DAYS).contains(d))
+                            {
+                                set_days.set(picked);
+                            }
+                        }
+                    />
+                </label>
+            </div>
+        </Show>
+        <div id="my_comparison" inner_html=comparison_svg />
+        {move || comparison.with(|c| view! {
+            <ComparisonSummary comparison=c.clone() locale=locale.get()/>
+        })}
+    }
+    .into_any()
+}
 
-        for s in series {
-            chart.draw_series(s.point_size(2))?;
+/// The periods [`PeriodComparison`] compares: a preset, or custom periods
+/// which start on the picked days and run for the same number of days.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Periods {
+    Preset(PeriodPreset),
+    Custom,
+}
+
+impl Periods {
+    const CUSTOM: &'static str = "custom";
+}
+
+impl fmt::Display for Periods {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Periods::Preset(preset) => preset.fmt(f),
+            Periods::Custom => f.write_str(Periods::CUSTOM),
         }
+    }
+}
+
+impl FromStr for Periods {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            Periods::CUSTOM => Ok(Periods::Custom),
+            _ => s.parse().map(Periods::Preset),
+        }
+    }
+}
+
+/// Total and mean daily downloads of both compared periods.
+#[component]
+#[allow(non_snake_case)]
+fn ComparisonSummary(comparison: Comparison, locale: Locale) -> impl IntoView {
+    let rows = [
+        ("Current", comparison.current),
+        ("Previous", comparison.previous),
+    ];
+
+    view! {
+        <table class="comparison">
+            <thead>
+                <tr>
+                    <th></th>
+                    <th>"Dates"</th>
+                    <th>"Total"</th>
+                    <th>"Mean per day"</th>
+                </tr>
+            </thead>
+            <tbody>
+                {rows
+                    .map(|(label, period)| view! {
+                        <tr>
+                            <th>{label}</th>
+                            <td>{period.period.describe(locale)}</td>
+                            <td>{locale.number(period.total)}</td>
+                            <td>
+                                {period
+                                    .mean
+                                    .map(|mean| locale.decimal(mean, 0))
+                                    .unwrap_or_else(|| "-".to_string())}
+                            </td>
+                        </tr>
+                    })
+                    .collect_view()}
+            </tbody>
+        </table>
+        <p>
+            "Change in mean daily downloads: "
+            {format_percent(comparison.change, locale)}
+        </p>
+    }
+}
 
-        root.present()?;
+/// Recent and overall growth figures, one column per source.
+#[component]
+#[allow(non_snake_case)]
+fn GrowthPanel(
+    growth: Vec<GrowthStats>,
+    locale: Locale,
+    time_zone: Tz,
+) -> impl IntoView {
+    let rows: [(&str, fn(&GrowthStats, Locale, Tz) -> String); 8] = [
+        ("Last 24 hours", |g, l, _| format_count(g.last_day, l)),
+        ("Last 7 days", |g, l, _| format_count(g.last_week, l)),
+        ("Last 30 days", |g, l, _| format_count(g.last_month, l)),
+        ("Average per day", |g, l, _| {
+            g.average_daily
+                .map(|a| l.decimal(a, 0))
+                .unwrap_or_else(|| "-".to_string())
+        }),
+        ("Week over week", |g, l, _| {
+            format_percent(g.week_over_week, l)
+        }),
+        ("Month over month", |g, l, _| {
+            format_percent(g.month_over_month, l)
+        }),
+        ("Best day", |g, l, tz| match g.best_day {
+            Some((day, downloads)) => {
+                format!("{} on {}", l.number(downloads), l.date_at(day, tz))
+            }
+            None => "-".to_string(),
+        }),
+        ("Days tracked", |g, l, _| l.number(g.days_tracked)),
+    ];
+
+    view! {
+        <table class="growth">
+            <thead>
+                <tr>
+                    <th></th>
+                    {growth
+                        .iter()
+                        .map(|g| view! { <th>{g.source.label()}</th> })
+                        .collect_view()}
+                </tr>
+            </thead>
+            <tbody>
+                {rows
+                    .into_iter()
+                    .map(|(label, value)| view! {
+                        <tr>
+                            <th>{label}</th>
+                            {growth
+                                .iter()
+                                .map(|g| view! {
+                                    <td>{value(g, locale, time_zone)}</td>
+                                })
+                                .collect_view()}
+                        </tr>
+                    })
+                    .collect_view()}
+            </tbody>
+        </table>
+    }
+}
+
+fn format_count(count: Option<i64>, locale: Locale) -> String {
+    count
+        .map(|c| locale.number(c))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn format_percent(percent: Option<f64>, locale: Locale) -> String {
+    percent
+        .map(|p| format!("{}%", locale.signed(p, 1)))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Lists the unusual stretches shaded on the chart.
+#[component]
+#[allow(non_snake_case)]
+fn AnomalyTable(
+    anomalies: Vec<Anomaly>,
+    locale: Locale,
+    time_zone: Tz,
+) -> impl IntoView {
+    if anomalies.is_empty() {
+        return view! { <p>"No unusual activity detected."</p> }.into_any();
     }
-    Ok(svg_content)
+
+    view! {
+        <h4>"Unusual activity"</h4>
+        <table class="anomalies">
+            <thead>
+                <tr>
+                    <th>"Source"</th>
+                    <th>"Kind"</th>
+                    <th>"From"</th>
+                    <th>"To"</th>
+                    <th>"Magnitude"</th>
+                </tr>
+            </thead>
+            <tbody>
+                {anomalies
+                    .into_iter()
+                    .map(|anomaly| view! {
+                        <tr>
+                            <td>{anomaly.source.label()}</td>
+                            <td>{anomaly.kind.label()}</td>
+                            <td>{locale.date_at(anomaly.start, time_zone)}</td>
+                            <td>{locale.date_at(anomaly.end, time_zone)}</td>
+                            <td>{anomaly.describe(locale)}</td>
+                        </tr>
+                    })
+                    .collect_view()}
+            </tbody>
+        </table>
+    }
+    .into_any()
+}
+
+/// Lists the stretches with no data, which the chart bridges with dashed
+/// lines rather than drawing as measured.
+#[component]
+#[allow(non_snake_case)]
+fn GapTable(gaps: Vec<Gap>, locale: Locale, time_zone: Tz) -> impl IntoView {
+    if gaps.is_empty() {
+        return ().into_any();
+    }
+
+    view! {
+        <h4>"Missing data"</h4>
+        <table class="gaps">
+            <thead>
+                <tr>
+                    <th>"Source"</th>
+                    <th>"From"</th>
+                    <th>"To"</th>
+                    <th>"Days"</th>
+                    <th>"Downloads across the gap"</th>
+                </tr>
+            </thead>
+            <tbody>
+                {gaps
+                    .into_iter()
+                    .map(|gap| view! {
+                        <tr>
+                            <td>{gap.source.label()}</td>
+                            <td>{locale.date_at(gap.start, time_zone)}</td>
+                            <td>{locale.date_at(gap.end, time_zone)}</td>
+                            <td>{locale.decimal(gap.days(), 1)}</td>
+                            <td>
+                                {locale.number(gap.end_downloads - gap.start_downloads)}
+                            </td>
+                        </tr>
+                    })
+                    .collect_view()}
+            </tbody>
+        </table>
+    }
+    .into_any()
+}
+
+/// The overall series as `(downloads, timestamp)` pairs, resampled to
+/// `bucket` in `time_zone` if one is picked.
+fn data_points(
+    mod_stats: &ModStatsResponse,
+    bucket: Option<Bucket>,
+    time_zone: Tz,
+) -> Vec<(i64, String)> {
+    let Some(bucket) = bucket else {
+        return mod_stats
+            .overall_stats
+            .iter()
+            .map(|s| (s.downloads, s.timestamp.clone()))
+            .collect();
+    };
+    resample_source(
+        mod_stats,
+        Source::Overall,
+        bucket,
+        Fill::default(),
+        DEFAULT_GAP_FACTOR,
+        time_zone,
+    )
+    .into_iter()
+    .filter_map(|(t, downloads)| {
+        // with the zone's offset, so buckets read as starting at midnight
+        let timestamp = DateTime::from_timestamp_millis(t)?
+            .with_timezone(&time_zone)
+            .to_rfc3339_opts(SecondsFormat::Secs, true);
+        Some((downloads, timestamp))
+    })
+    .collect()
+}
+
+fn render_chart(
+    mod_stats: &ModStatsResponse,
+    options: &ChartOptions,
+) -> String {
+    if mod_stats.overall_stats.is_empty() {
+        return String::new();
+    }
+
+    tracing::debug!(
+        overall = mod_stats.overall_stats.len(),
+        modrinth = mod_stats.modrinth_stats.len(),
+        curse_forge = mod_stats.curse_forge_stats.len(),
+        "rendering chart data points"
+    );
+
+    mod_stats_svg(mod_stats, options).unwrap_or_else(|err| {
+        tracing::error!(error = %err, "failed to render downloads chart");
+        String::new()
+    })
+}
+
+/// A chart option kept in the page's `key` query parameter, so that a reload
+/// or a copied link shows the same view. Changing it replaces the current
+/// history entry rather than adding one, and keeps the scroll position.
+fn replace_query<T>(
+    key: &'static str,
+) -> (Memo<Option<T>>, SignalSetter<Option<T>>)
+where
+    T: FromStr + ToString + PartialEq + Send + Sync + 'static,
+{
+    query_signal_with_options(
+        key,
+        NavigateOptions {
+            replace: true,
+            scroll: false,
+            ..Default::default()
+        },
+    )
+}
+
+/// Like [`replace_query`], for an option with a default. The parameter is
+/// left out of the URL while the option has its default, and a missing or
+/// unparsable one reads as the default.
+fn query_option<T>(
+    key: &'static str,
+    default: T,
+) -> (Signal<T>, SignalSetter<T>)
+where
+    T: FromStr + ToString + PartialEq + Clone + Send + Sync + 'static,
+{
+    let (value, set_value) = replace_query::<T>(key);
+    let fallback = default.clone();
+    (
+        Signal::derive(move || value.get().unwrap_or_else(|| fallback.clone())),
+        SignalSetter::map(move |picked: T| {
+            set_value.set((picked != default).then_some(picked))
+        }),
+    )
 }
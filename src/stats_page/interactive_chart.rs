@@ -0,0 +1,503 @@
+//! The downloads chart and the ways to explore it.
+//!
+//! With a pointer: drag across the chart to zoom to the selection, scroll to
+//! zoom around the pointer (or pan, scrolling sideways), pinch on touch
+//! screens, and drag the highlighted window along the navigator strip
+//! underneath.
+//!
+//! With a keyboard: the chart takes focus, the arrow keys step through its
+//! points, announced to screen readers through a live region, and `+`, `-`
+//! and `0` zoom.
+//!
+//! The window zoomed to is kept in the page's `range` query parameter, like
+//! the other chart options. The chart is re-rendered as a gesture moves, but
+//! the URL is only replaced once it ends, or once scrolling or key presses
+//! pause, as browsers limit how often it may be.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono_tz::Tz;
+use leptos::ev::{KeyboardEvent, PointerEvent, WheelEvent};
+use leptos::html::Div;
+use leptos::prelude::{
+    on_cleanup, set_timeout_with_handle, AriaAttributes, ClassAttribute,
+    Effect, ElementChild, Get, GetUntracked, GlobalAttributes,
+    InnerHtmlAttribute, NodeRef, NodeRefAttribute, OnAttribute, RwSignal, Set,
+    SetValue, Signal, StoredValue, StyleAttribute, TimeoutHandle, Track,
+    UpdateValue, With, WithValue,
+};
+use leptos::reactive::wrappers::write::SignalSetter;
+use leptos::*;
+
+use crate::analysis::transform::DisplayMode;
+use crate::analysis::Source;
+use crate::chart::{PlottedSeries, TimeAxis, TimeWindow};
+use crate::format::Locale;
+
+/// Drags shorter than this many pixels are clicks, not selections.
+const MIN_BRUSH_PX: f64 = 8.0;
+
+/// How long scrolling or zoom keys must pause before the window shown is
+/// written to the URL.
+const COMMIT_DELAY: Duration = Duration::from_millis(300);
+
+/// What the pointers on the chart are doing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Gesture {
+    Idle,
+    /// Selecting a window from client x `from` to `to`, on a chart whose
+    /// container starts at client x `left`.
+    Brush {
+        from: f64,
+        to: f64,
+        left: f64,
+    },
+    /// Two pointers, `distance` pixels apart when `start` was shown, pinching
+    /// around time `center`.
+    Pinch {
+        distance: f64,
+        start: TimeWindow,
+        center: i64,
+    },
+    /// Dragging the navigator's window, held `offset` milliseconds after its
+    /// start.
+    Pan {
+        offset: i64,
+    },
+}
+
+/// A point of the chart as the keyboard steps through it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartPoint {
+    pub source: Source,
+    pub t: i64,
+    /// What a screen reader announces when the point is reached.
+    pub description: String,
+}
+
+/// The points of `series`, in the order their circles are drawn, described
+/// for `locale` with times in `time_zone`.
+pub fn chart_points(
+    series: &[PlottedSeries],
+    mode: DisplayMode,
+    locale: Locale,
+    time_zone: Tz,
+) -> Vec<ChartPoint> {
+    series
+        .iter()
+        .flat_map(|plotted| {
+            plotted.segments.iter().flatten().map(move |&(t, value)| {
+                let time = format!(
+                    "{} {time_zone}",
+                    locale.date_time_at(t, time_zone)
+                );
+                ChartPoint {
+                    source: plotted.source,
+                    t,
+                    description: format!(
+                        "{}, {time}: {}",
+                        plotted.source.label(),
+                        mode.describe_value(value, locale)
+                    ),
+                }
+            })
+        })
+        .collect()
+}
+
+/// The chart with its zoom gestures, keyboard navigation, navigator strip,
+/// reset button and `summary`. `bounds` is the whole history, shown when
+/// `range` is unset. `range` changes as gestures move, and is passed to
+/// `commit_range` once they end.
+#[component]
+#[allow(non_snake_case)]
+pub fn InteractiveChart(
+    chart_svg: Signal<String>,
+    navigator_svg: Signal<String>,
+    points: Signal<Vec<ChartPoint>>,
+    summary: Signal<String>,
+    bounds: TimeWindow,
+    range: RwSignal<Option<TimeWindow>>,
+    commit_range: SignalSetter<Option<TimeWindow>>,
+) -> impl IntoView {
+    let container = NodeRef::<Div>::new();
+    let plot = NodeRef::<Div>::new();
+    let navigator = NodeRef::<Div>::new();
+    let gesture = RwSignal::new(Gesture::Idle);
+    // client x of each pointer down on the chart
+    let pointers = StoredValue::new(HashMap::<i32, f64>::new());
+    let pending_commit = StoredValue::new(None::<TimeoutHandle>);
+
+    let window = move || range.get().map_or(bounds, |r| r.within(bounds));
+    // the whole history is the default view, left out of the URL
+    let show = move |shown: TimeWindow| {
+        let shown = shown.within(bounds);
+        range.set((shown != bounds).then_some(shown))
+    };
+    let commit = move || {
+        if let Some(handle) = pending_commit.get_value() {
+            handle.clear();
+            pending_commit.set_value(None);
+        }
+        commit_range.set(range.get_untracked());
+    };
+    // for steps that come in bursts, like scrolling or a held key
+    let commit_soon = move || {
+        if let Some(handle) = pending_commit.get_value() {
+            handle.clear();
+        }
+        let handle = set_timeout_with_handle(commit, COMMIT_DELAY).ok();
+        pending_commit.set_value(handle);
+    };
+    let reset = move || {
+        range.set(None);
+        commit();
+    };
+    on_cleanup(move || {
+        if let Some(handle) = pending_commit.get_value() {
+            handle.clear();
+        }
+    });
+    let time_at = move |node: NodeRef<Div>, client_x: f64| {
+        let (axis, x) = axis_at(&node.get()?, client_x)?;
+        Some(axis.time_at(x))
+    };
+
+    let on_pointer_down = move |ev: PointerEvent| {
+        if ev.pointer_type() == "mouse" && ev.button() != 0 {
+            return;
+        }
+        let Some(el) = container.get() else {
+            return;
+        };
+        let _ = el.set_pointer_capture(ev.pointer_id());
+        let client_x = ev.client_x() as f64;
+        pointers.update_value(|p| {
+            p.insert(ev.pointer_id(), client_x);
+        });
+
+        let xs: Vec<f64> =
+            pointers.with_value(|p| p.values().copied().collect());
+        match xs[..] {
+            [_] => gesture.set(Gesture::Brush {
+                from: client_x,
+                to: client_x,
+                left: el.get_bounding_client_rect().left(),
+            }),
+            [a, b] => {
+                let Some(center) = time_at(plot, (a + b) / 2.0) else {
+                    return;
+                };
+                gesture.set(Gesture::Pinch {
+                    distance: (a - b).abs().max(1.0),
+                    start: window(),
+                    center,
+                });
+            }
+            _ => gesture.set(Gesture::Idle),
+        }
+    };
+
+    let on_pointer_move = move |ev: PointerEvent| {
+        let client_x = ev.client_x() as f64;
+        let tracked = pointers.with_value(|p| p.contains_key(&ev.pointer_id()));
+        if !tracked {
+            return;
+        }
+        pointers.update_value(|p| {
+            p.insert(ev.pointer_id(), client_x);
+        });
+
+        match gesture.get() {
+            Gesture::Brush { from, left, .. } => gesture.set(Gesture::Brush {
+                from,
+                to: client_x,
+                left,
+            }),
+            Gesture::Pinch {
+                distance,
+                start,
+                center,
+            } => {
+                let xs: Vec<f64> =
+                    pointers.with_value(|p| p.values().copied().collect());
+                if let [a, b] = xs[..] {
+                    let factor = distance / (a - b).abs().max(1.0);
+                    show(start.zoom(factor, center, bounds));
+                }
+            }
+            Gesture::Idle | Gesture::Pan { .. } => {}
+        }
+    };
+
+    let on_pointer_up = move |ev: PointerEvent| {
+        pointers.update_value(|p| {
+            p.remove(&ev.pointer_id());
+        });
+        match gesture.get() {
+            Gesture::Brush { from, to, .. } => {
+                if (to - from).abs() >= MIN_BRUSH_PX {
+                    if let (Some(a), Some(b)) =
+                        (time_at(plot, from), time_at(plot, to))
+                    {
+                        show(TimeWindow {
+                            start: a.min(b),
+                            end: a.max(b),
+                        });
+                    }
+                }
+                commit();
+            }
+            Gesture::Pinch { .. } => commit(),
+            Gesture::Idle | Gesture::Pan { .. } => {}
+        }
+        gesture.set(Gesture::Idle);
+    };
+
+    let on_wheel = move |ev: WheelEvent| {
+        let Some((axis, x)) =
+            plot.get().and_then(|el| axis_at(&el, ev.client_x() as f64))
+        else {
+            return;
+        };
+        ev.prevent_default();
+        let shown = window();
+        if ev.delta_x().abs() > ev.delta_y().abs() || ev.shift_key() {
+            let delta = match ev.delta_x() {
+                0.0 => ev.delta_y(),
+                delta => delta,
+            };
+            let width = (axis.right - axis.left).max(1.0);
+            show(
+                shown.pan((delta / width * shown.span() as f64) as i64, bounds),
+            );
+        } else {
+            show(shown.zoom(
+                (ev.delta_y() * 0.002).exp(),
+                axis.time_at(x),
+                bounds,
+            ));
+        }
+        commit_soon();
+    };
+
+    let on_navigator_down = move |ev: PointerEvent| {
+        let Some(el) = navigator.get() else {
+            return;
+        };
+        let Some(t) = time_at(navigator, ev.client_x() as f64) else {
+            return;
+        };
+        let _ = el.set_pointer_capture(ev.pointer_id());
+        let shown = window();
+        // grab the window where it was pressed, or centre it on the press
+        let offset = match shown.contains(t) {
+            true => t - shown.start,
+            false => shown.span() / 2,
+        };
+        show(shown.pan(t - offset - shown.start, bounds));
+        gesture.set(Gesture::Pan { offset });
+    };
+
+    let on_navigator_up = move |_| {
+        if let Gesture::Pan { .. } = gesture.get() {
+            commit();
+        }
+        gesture.set(Gesture::Idle);
+    };
+
+    let on_navigator_move = move |ev: PointerEvent| {
+        let Gesture::Pan { offset } = gesture.get() else {
+            return;
+        };
+        if let Some(t) = time_at(navigator, ev.client_x() as f64) {
+            let shown = window();
+            show(shown.pan(t - offset - shown.start, bounds));
+        }
+    };
+
+    // the point reached with the keyboard, kept by identity so it survives
+    // the chart being re-rendered
+    let focused = RwSignal::new(None::<(Source, i64)>);
+    let announcement = RwSignal::new(String::new());
+
+    let on_key_down = move |ev: KeyboardEvent| {
+        let key = ev.key();
+        let zoom_around = |factor: f64| {
+            let shown = window();
+            let around = focused
+                .get()
+                .map_or(shown.start + shown.span() / 2, |(_, t)| t);
+            show(shown.zoom(factor, around, bounds));
+            commit_soon();
+        };
+        match key.as_str() {
+            "+" | "=" => zoom_around(0.5),
+            "-" => zoom_around(2.0),
+            "0" => reset(),
+            "ArrowLeft" | "ArrowRight" | "ArrowUp" | "ArrowDown" | "Home"
+            | "End" => {
+                let reached = points.with(|points| {
+                    step(points, focused.get(), &key).map(|idx| {
+                        let point = &points[idx];
+                        ((point.source, point.t), point.description.clone())
+                    })
+                });
+                if let Some((point, description)) = reached {
+                    focused.set(Some(point));
+                    announcement.set(description);
+                }
+            }
+            _ => return,
+        }
+        ev.prevent_default();
+    };
+
+    // outlines the focused point's circle, which is drawn in the same order
+    // as `points`
+    Effect::new(move |_| {
+        chart_svg.track();
+        let Some(el) = plot.get() else {
+            return;
+        };
+        if let Ok(Some(previous)) = el.query_selector("circle.focused") {
+            let _ = previous.remove_attribute("class");
+        }
+        let idx = focused.get().and_then(|(source, t)| {
+            points.with(|points| {
+                points.iter().position(|p| p.source == source && p.t == t)
+            })
+        });
+        if let Some(circle) = idx.and_then(|idx| {
+            el.get_elements_by_tag_name("circle").item(idx as u32)
+        }) {
+            let _ = circle.set_attribute("class", "focused");
+        }
+    });
+
+    let brush_style = move || match gesture.get() {
+        Gesture::Brush { from, to, left } if from != to => format!(
+            "display: block; left: {}px; width: {}px;",
+            from.min(to) - left,
+            (to - from).abs()
+        ),
+        _ => "display: none;".to_string(),
+    };
+
+    view! {
+        <div class="interactive_chart">
+            <div
+                id="my_plot_container"
+                node_ref=container
+                tabindex="0"
+                role="application"
+                aria-label="Downloads chart"
+                aria-describedby="my_plot_summary my_plot_keys"
+                on:pointerdown=on_pointer_down
+                on:pointermove=on_pointer_move
+                on:pointerup=on_pointer_up
+                on:pointercancel=on_pointer_up
+                on:wheel=on_wheel
+                on:keydown=on_key_down
+            >
+                <div id="my_plot" node_ref=plot inner_html=move || chart_svg.get() />
+                <div id="my_plot_brush" style=brush_style />
+                <div id="my_plot_tooltip" />
+            </div>
+            <p id="my_plot_summary" class="chart_summary">{move || summary.get()}</p>
+            <p id="my_plot_keys" class="visually_hidden">
+                "Use the left and right arrow keys to move between points, up \
+                 and down to switch series, plus and minus to zoom and 0 to \
+                 reset the zoom."
+            </p>
+            <div id="my_plot_status" class="visually_hidden" aria-live="polite">
+                {move || announcement.get()}
+            </div>
+            <div
+                id="my_plot_navigator"
+                node_ref=navigator
+                aria-hidden="true"
+                on:pointerdown=on_navigator_down
+                on:pointermove=on_navigator_move
+                on:pointerup=on_navigator_up
+                on:pointercancel=on_navigator_up
+                inner_html=move || navigator_svg.get()
+            />
+            <button
+                on:click=move |_| reset()
+                disabled=move || range.get().is_none()
+            >
+                "Reset zoom"
+            </button>
+        </div>
+    }
+}
+
+/// The index in `points` reached by pressing `key` on the point `from`: the
+/// previous, next, first or last point of the same series, or the nearest
+/// point in time of the series above or below. Starts from the first series
+/// when nothing is focused, or the focused series is no longer plotted.
+fn step(
+    points: &[ChartPoint],
+    from: Option<(Source, i64)>,
+    key: &str,
+) -> Option<usize> {
+    let mut sources: Vec<Source> = points.iter().map(|p| p.source).collect();
+    sources.dedup();
+    let in_series = |source: Source| -> Vec<usize> {
+        (0..points.len())
+            .filter(|&idx| points[idx].source == source)
+            .collect()
+    };
+    let nearest = |indices: &[usize], t: i64| {
+        indices
+            .iter()
+            .copied()
+            .min_by_key(|&idx| (points[idx].t - t).abs())
+    };
+
+    let Some((source, t)) = from.filter(|(source, _)| sources.contains(source))
+    else {
+        let first = in_series(*sources.first()?);
+        return match key {
+            "ArrowLeft" | "End" => first.last().copied(),
+            _ => first.first().copied(),
+        };
+    };
+    let series = in_series(source);
+    let current = series
+        .iter()
+        .position(|&idx| Some(idx) == nearest(&series, t))?;
+    match key {
+        "ArrowLeft" => series.get(current.saturating_sub(1)).copied(),
+        "ArrowRight" => series.get(current + 1).or(series.last()).copied(),
+        "Home" => series.first().copied(),
+        "End" => series.last().copied(),
+        "ArrowUp" | "ArrowDown" => {
+            let at = sources.iter().position(|s| *s == source)?;
+            let other = match key {
+                "ArrowUp" => at.checked_sub(1)?,
+                _ => at + 1,
+            };
+            nearest(&in_series(*sources.get(other)?), t)
+        }
+        _ => None,
+    }
+}
+
+/// The time axis of the chart rendered in `el`, and client x `client_x` as a
+/// pixel of the chart, which may be drawn scaled.
+fn axis_at(
+    el: &web_sys::HtmlDivElement,
+    client_x: f64,
+) -> Option<(TimeAxis, f64)> {
+    let svg = el.first_element_child()?;
+    let axis = TimeAxis::from_attributes(|name| svg.get_attribute(name))?;
+    let width: f64 = svg.get_attribute("width")?.parse().ok()?;
+    let rect = svg.get_bounding_client_rect();
+    if rect.width() <= 0.0 {
+        return None;
+    }
+    Some((axis, (client_x - rect.left()) * width / rect.width()))
+}
@@ -0,0 +1,24 @@
+//! The browser's preferred language, as the locale the page formats dates
+//! and numbers for when none is picked.
+
+use leptos::prelude::{window, Effect, RwSignal, Set, Signal};
+
+use crate::format::Locale;
+
+/// The browser's locale, once the page is running in it. Until then, and
+/// throughout SSR, there is none, so hydration sees the same text the server
+/// wrote.
+pub fn use_browser_locale() -> Signal<Option<Locale>> {
+    let locale = RwSignal::new(None::<Locale>);
+    Effect::new(move |_| {
+        let navigator = window().navigator();
+        let preferred = navigator
+            .languages()
+            .iter()
+            .filter_map(|tag| tag.as_string())
+            .chain(navigator.language())
+            .find_map(|tag| Locale::negotiate(&tag));
+        locale.set(preferred);
+    });
+    locale.into()
+}
@@ -0,0 +1,55 @@
+//! The site theme's colours, as the charts are drawn in them.
+//!
+//! Themes come from the multi-palette script loaded by the app, which sets
+//! them as CSS custom properties and swaps them when the "Theme" button is
+//! clicked. Plotters only draws fixed colours, so the properties are read
+//! back here and the charts re-rendered with them.
+
+use leptos::ev;
+use leptos::prelude::{
+    document, on_cleanup, request_animation_frame, window,
+    window_event_listener, Effect, Get, Memo, RwSignal, Set, Signal,
+};
+use wasm_bindgen::JsCast;
+
+use crate::chart::palette::ThemeColors;
+
+/// The class of the app's button for swapping themes.
+const SWAP_BUTTON: &str = ".swap_palette";
+
+/// The active theme's colours, once the page is running in the browser. Until
+/// then, and throughout SSR, there are none, so hydration sees the same
+/// charts the server drew.
+pub fn use_theme_colors() -> Signal<Option<ThemeColors>> {
+    let theme = RwSignal::new(None::<ThemeColors>);
+    // after the swap button's own handler, and the next style recalculation
+    let reread = move || request_animation_frame(move || theme.set(read()));
+
+    Effect::new(move |_| reread());
+    let handle = window_event_listener(ev::click, move |ev| {
+        let on_swap_button = ev
+            .target()
+            .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+            .and_then(|el| el.closest(SWAP_BUTTON).ok().flatten())
+            .is_some();
+        if on_swap_button {
+            reread();
+        }
+    });
+    on_cleanup(move || handle.remove());
+
+    // unchanged colours don't re-render the charts
+    Memo::new(move |_| theme.get()).into()
+}
+
+/// Reads the theme's custom properties off the page's body.
+fn read() -> Option<ThemeColors> {
+    let body = document().body()?;
+    let style = window().get_computed_style(&body).ok()??;
+    Some(ThemeColors::from_tokens(|name| {
+        style
+            .get_property_value(name)
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+    }))
+}
@@ -0,0 +1,23 @@
+//! The browser's time zone, as the one the page writes times and counts days
+//! in when none is picked.
+
+use chrono_tz::Tz;
+use js_sys::{Array, Intl, Object, Reflect};
+use leptos::prelude::{Effect, RwSignal, Set, Signal};
+
+/// The browser's time zone, once the page is running in it. Until then, and
+/// throughout SSR, there is none, so hydration sees the same text the server
+/// wrote.
+pub fn use_browser_time_zone() -> Signal<Option<Tz>> {
+    let time_zone = RwSignal::new(None::<Tz>);
+    Effect::new(move |_| {
+        let options = Intl::DateTimeFormat::new(&Array::new(), &Object::new())
+            .resolved_options();
+        let browser = Reflect::get(&options, &"timeZone".into())
+            .ok()
+            .and_then(|name| name.as_string())
+            .and_then(|name| name.parse().ok());
+        time_zone.set(browser);
+    });
+    time_zone.into()
+}